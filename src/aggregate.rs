@@ -0,0 +1,236 @@
+use crate::{
+    condition::ConditionExpr,
+    expr::Expr,
+    query::{ColumnOutOfRangeError, LineProcessError},
+    row::Row,
+    value::{BinaryOp, Value},
+};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateKind {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    CountDistinct,
+}
+
+impl AggregateKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "count" => AggregateKind::Count,
+            "sum" => AggregateKind::Sum,
+            "avg" => AggregateKind::Avg,
+            "min" => AggregateKind::Min,
+            "max" => AggregateKind::Max,
+            "count_distinct" => AggregateKind::CountDistinct,
+            _ => return None,
+        })
+    }
+
+    /// `count()` counts all rows, other aggregates need exactly one argument.
+    pub fn accepts_arg_count(self, count: usize) -> bool {
+        match self {
+            AggregateKind::Count => count <= 1,
+            _ => count == 1,
+        }
+    }
+}
+
+/// An aggregate function call, e.g. `sum(RSS)`. The argument is evaluated for input rows.
+#[derive(Debug)]
+pub struct Aggregate {
+    pub kind: AggregateKind,
+    pub arg: Option<Expr>,
+}
+
+#[derive(Debug)]
+pub struct GroupBy {
+    /// Empty, if aggregates are used without `group by`: all rows form a single group then
+    pub keys: Vec<Expr>,
+    pub aggregates: Vec<Aggregate>,
+    /// The `if` condition. Is checked for input rows, before grouping.
+    pub filter: ConditionExpr,
+}
+
+/// Groups rows. Each group is represented by its first row, with results of aggregate functions
+/// attached.
+pub struct Aggregator<'a> {
+    group_by: &'a GroupBy,
+    group_ids: HashMap<Vec<String>, usize>,
+    /// In order of appearance
+    groups: Vec<(Row, Vec<Accumulator>)>,
+}
+
+impl<'a> Aggregator<'a> {
+    pub fn new(group_by: &'a GroupBy) -> Self {
+        Self {
+            group_by,
+            group_ids: HashMap::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: &Row) -> Result<(), LineProcessError> {
+        if !self.group_by.filter.check(row) {
+            return Ok(());
+        }
+
+        // Arguments are checked beforehand, so that a bad row doesn't update a group partially
+        for arg in self
+            .group_by
+            .aggregates
+            .iter()
+            .filter_map(|aggregate| aggregate.arg.as_ref())
+        {
+            arg.eval_text(row)?;
+        }
+
+        let key = self
+            .group_by
+            .keys
+            .iter()
+            .map(|key| Ok(key.eval_text(row)?.into_owned()))
+            .collect::<Result<Vec<_>, ColumnOutOfRangeError>>()?;
+
+        let group_id = match self.group_ids.entry(key) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                self.groups.push((
+                    Row::new(row.cells().to_vec()).with_line_num(row.line_num()),
+                    self.group_by
+                        .aggregates
+                        .iter()
+                        .map(|aggregate| Accumulator::new(aggregate.kind))
+                        .collect(),
+                ));
+                *entry.insert(self.groups.len() - 1)
+            }
+        };
+
+        let accumulators = &mut self.groups[group_id].1;
+        for (accumulator, aggregate) in accumulators.iter_mut().zip(&self.group_by.aggregates) {
+            accumulator.push(aggregate, row)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> impl Iterator<Item = Row> {
+        // Like in SQL, aggregates without `group by` result in a single row, even for an empty input
+        if self.groups.is_empty() && self.group_by.keys.is_empty() {
+            self.groups.push((
+                Row::new(Vec::new()),
+                self.group_by
+                    .aggregates
+                    .iter()
+                    .map(|aggregate| Accumulator::new(aggregate.kind))
+                    .collect(),
+            ));
+        }
+
+        self.groups.into_iter().map(|(row, accumulators)| {
+            row.with_aggregates(accumulators.into_iter().map(Accumulator::finish).collect())
+        })
+    }
+}
+
+/// State of an aggregate function for a single group. Empty values are skipped.
+enum Accumulator {
+    Count(usize),
+    Sum(Option<Value>),
+    Avg { sum: Option<Value>, count: usize },
+    Min(Option<Value>),
+    Max(Option<Value>),
+    CountDistinct(HashSet<String>),
+}
+
+impl Accumulator {
+    fn new(kind: AggregateKind) -> Self {
+        match kind {
+            AggregateKind::Count => Accumulator::Count(0),
+            AggregateKind::Sum => Accumulator::Sum(None),
+            AggregateKind::Avg => Accumulator::Avg {
+                sum: None,
+                count: 0,
+            },
+            AggregateKind::Min => Accumulator::Min(None),
+            AggregateKind::Max => Accumulator::Max(None),
+            AggregateKind::CountDistinct => Accumulator::CountDistinct(HashSet::new()),
+        }
+    }
+
+    fn push(&mut self, aggregate: &Aggregate, row: &Row) -> Result<(), ColumnOutOfRangeError> {
+        let Some(arg) = &aggregate.arg else {
+            // `count()`
+            if let Accumulator::Count(count) = self {
+                *count += 1;
+            }
+            return Ok(());
+        };
+
+        if let Accumulator::Count(_) | Accumulator::CountDistinct(_) = self {
+            let text = arg.eval_text(row)?;
+            match self {
+                Accumulator::Count(count) if !text.is_empty() => *count += 1,
+                Accumulator::CountDistinct(texts)
+                    if !text.is_empty() && !texts.contains(text.as_ref()) =>
+                {
+                    texts.insert(text.into_owned());
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let value = arg.eval(row)?;
+        if let Value::Empty = *value {
+            return Ok(());
+        }
+
+        match self {
+            Accumulator::Sum(sum) => *sum = Some(add(sum.take(), &value)),
+            Accumulator::Avg { sum, count } => {
+                *sum = Some(add(sum.take(), &value));
+                *count += 1;
+            }
+            Accumulator::Min(min) => {
+                if min.as_ref().is_none_or(|min| value.compare(min).is_lt()) {
+                    *min = Some(value.into_owned());
+                }
+            }
+            Accumulator::Max(max) => {
+                if max.as_ref().is_none_or(|max| value.compare(max).is_gt()) {
+                    *max = Some(value.into_owned());
+                }
+            }
+            Accumulator::Count(_) | Accumulator::CountDistinct(_) => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        match self {
+            Accumulator::Count(count) => Value::Int(count as i64),
+            Accumulator::Sum(value) | Accumulator::Min(value) | Accumulator::Max(value) => {
+                value.unwrap_or(Value::Empty)
+            }
+            Accumulator::Avg {
+                sum: Some(sum),
+                count,
+            } => BinaryOp::Div.apply(&sum, &Value::Int(count as i64)),
+            Accumulator::Avg { sum: None, .. } => Value::Empty,
+            Accumulator::CountDistinct(texts) => Value::Int(texts.len() as i64),
+        }
+    }
+}
+
+fn add(sum: Option<Value>, value: &Value) -> Value {
+    match sum {
+        Some(sum) => BinaryOp::Add.apply(&sum, value),
+        None => value.clone(),
+    }
+}
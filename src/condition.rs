@@ -1,45 +1,306 @@
+use crate::{expr::Expr, query::ColumnOutOfRangeError, row::Row, value::Value};
+use regex::Regex;
+use std::{borrow::Cow, collections::HashSet};
+
+type CheckResult = Result<bool, ColumnOutOfRangeError>;
+
 #[derive(Debug)]
 pub enum ConditionExpr {
     Condition(Box<dyn Condition>),
     Or(Box<ConditionExpr>, Box<ConditionExpr>),
     And(Box<ConditionExpr>, Box<ConditionExpr>),
+    Not(Box<ConditionExpr>),
 }
 
 impl ConditionExpr {
-    pub fn check(&self, row: &Vec<String>) -> bool {
+    /// A condition, that references a column, missing in the row, is false: e.g. both `X = 1` and
+    /// `X != 1` are false for a row without `X`. `not` is applied to the result, so `not X = 1` is
+    /// true for such a row. Use `X is missing` to check for a column explicitly.
+    pub fn check(&self, row: &Row) -> bool {
         match &self {
-            ConditionExpr::Condition(cond) => cond.check(row),
+            ConditionExpr::Condition(cond) => cond.check(row).unwrap_or(false),
             ConditionExpr::Or(lhs, rhs) => lhs.check(row) || rhs.check(row),
             ConditionExpr::And(lhs, rhs) => lhs.check(row) && rhs.check(row),
+            ConditionExpr::Not(rhs) => !rhs.check(row),
         }
     }
 }
 
 pub trait Condition: std::fmt::Debug {
-    fn check(&self, row: &Vec<String>) -> bool;
+    fn check(&self, row: &Row) -> CheckResult;
+}
+
+/// Against a string literal texts are compared exactly: `VER = "1.1"` is false for `1.10`.
+/// Otherwise values are compared by their types: `VER = 1.1` is true for `1.10`.
+#[derive(Debug)]
+pub struct EqCondition(pub Expr, pub Expr);
+
+impl Condition for EqCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        equals(&self.0, &self.1, row)
+    }
+}
+
+#[derive(Debug)]
+pub struct NeCondition(pub Expr, pub Expr);
+
+impl Condition for NeCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(!equals(&self.0, &self.1, row)?)
+    }
+}
+
+fn equals(lhs: &Expr, rhs: &Expr, row: &Row) -> CheckResult {
+    if lhs.is_str() || rhs.is_str() {
+        let (lhs, rhs) = eval_texts(lhs, rhs, false, row)?;
+        Ok(lhs == rhs)
+    } else {
+        Ok(lhs.eval(row)? == rhs.eval(row)?)
+    }
+}
+
+#[derive(Debug)]
+pub struct LtCondition(pub Expr, pub Expr);
+
+impl Condition for LtCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(self.0.eval(row)?.compare(&*self.1.eval(row)?).is_lt())
+    }
+}
+
+#[derive(Debug)]
+pub struct LeCondition(pub Expr, pub Expr);
+
+impl Condition for LeCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(self.0.eval(row)?.compare(&*self.1.eval(row)?).is_le())
+    }
+}
+
+#[derive(Debug)]
+pub struct GtCondition(pub Expr, pub Expr);
+
+impl Condition for GtCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(self.0.eval(row)?.compare(&*self.1.eval(row)?).is_gt())
+    }
+}
+
+#[derive(Debug)]
+pub struct GeCondition(pub Expr, pub Expr);
+
+impl Condition for GeCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(self.0.eval(row)?.compare(&*self.1.eval(row)?).is_ge())
+    }
+}
+
+/// `=i`: case-insensitive comparison. Unlike `=`, values are compared as text.
+#[derive(Debug)]
+pub struct EqIgnoreCaseCondition(pub Expr, pub Expr);
+
+impl Condition for EqIgnoreCaseCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        let (lhs, rhs) = eval_texts(&self.0, &self.1, true, row)?;
+        Ok(lhs == rhs)
+    }
+}
+
+#[derive(Debug)]
+pub struct NeIgnoreCaseCondition(pub Expr, pub Expr);
+
+impl Condition for NeIgnoreCaseCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        let (lhs, rhs) = eval_texts(&self.0, &self.1, true, row)?;
+        Ok(lhs != rhs)
+    }
+}
+
+#[derive(Debug)]
+pub struct ContainsCondition {
+    pub haystack: Expr,
+    pub needle: Expr,
+    pub ignore_case: bool,
+}
+
+impl Condition for ContainsCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        let (haystack, needle) = eval_texts(&self.haystack, &self.needle, self.ignore_case, row)?;
+        Ok(haystack.contains(needle.as_ref()))
+    }
+}
+
+#[derive(Debug)]
+pub struct StartsWithCondition {
+    pub haystack: Expr,
+    pub needle: Expr,
+    pub ignore_case: bool,
+}
+
+impl Condition for StartsWithCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        let (haystack, needle) = eval_texts(&self.haystack, &self.needle, self.ignore_case, row)?;
+        Ok(haystack.starts_with(needle.as_ref()))
+    }
 }
 
 #[derive(Debug)]
-pub enum ConditionOperand {
-    ColumnId(usize),
-    Const(String),
+pub struct EndsWithCondition {
+    pub haystack: Expr,
+    pub needle: Expr,
+    pub ignore_case: bool,
 }
 
-impl ConditionOperand {
-    fn value<'a>(&'a self, row: &'a Vec<String>) -> &'a str {
-        match self {
-            ConditionOperand::ColumnId(col_id) => &row[*col_id],
-            ConditionOperand::Const(c) => c,
+impl Condition for EndsWithCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        let (haystack, needle) = eval_texts(&self.haystack, &self.needle, self.ignore_case, row)?;
+        Ok(haystack.ends_with(needle.as_ref()))
+    }
+}
+
+/// Evaluates both expressions as text, lowercasing it, if the case should be ignored.
+fn eval_texts<'a>(
+    lhs: &'a Expr,
+    rhs: &'a Expr,
+    ignore_case: bool,
+    row: &'a Row,
+) -> Result<(Cow<'a, str>, Cow<'a, str>), ColumnOutOfRangeError> {
+    let lhs = lhs.eval_text(row)?;
+    let rhs = rhs.eval_text(row)?;
+
+    Ok(if ignore_case {
+        (
+            Cow::Owned(lhs.to_lowercase()),
+            Cow::Owned(rhs.to_lowercase()),
+        )
+    } else {
+        (lhs, rhs)
+    })
+}
+
+/// Checks if the regex matches any part of the value.
+#[derive(Debug)]
+pub struct MatchCondition(pub Expr, pub Regex);
+
+impl Condition for MatchCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(self.1.is_match(&self.0.eval_text(row)?))
+    }
+}
+
+#[derive(Debug)]
+pub struct NotMatchCondition(pub Expr, pub Regex);
+
+impl Condition for NotMatchCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(!self.1.is_match(&self.0.eval_text(row)?))
+    }
+}
+
+/// A list of values of `in (...)`.
+#[derive(Debug)]
+pub struct ValueSet {
+    /// Constant items, that are looked up fast
+    values: HashSet<Value>,
+    /// Items, that depend on a row
+    exprs: Vec<Expr>,
+}
+
+impl ValueSet {
+    pub fn new(items: Vec<Expr>) -> Self {
+        let (consts, exprs): (Vec<_>, Vec<_>) = items.into_iter().partition(Expr::is_const);
+
+        let empty_row = Row::new(Vec::new());
+        let values = consts
+            .iter()
+            .map(|expr| expr.eval(&empty_row).unwrap().into_owned())
+            .collect();
+
+        Self { values, exprs }
+    }
+
+    fn contains(&self, value: &Value, row: &Row) -> CheckResult {
+        if self.values.contains(value) {
+            return Ok(true);
+        }
+
+        for expr in self.exprs.iter() {
+            if *expr.eval(row)? == *value {
+                return Ok(true);
+            }
         }
+
+        Ok(false)
     }
 }
 
 #[derive(Debug)]
-pub struct EqCondition(pub ConditionOperand, pub ConditionOperand);
+pub struct InCondition(pub Expr, pub ValueSet);
 
-impl Condition for EqCondition {
-    fn check(&self, row: &Vec<String>) -> bool {
-        self.0.value(row) == self.1.value(row)
+impl Condition for InCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        self.1.contains(&*self.0.eval(row)?, row)
+    }
+}
+
+#[derive(Debug)]
+pub struct NotInCondition(pub Expr, pub ValueSet);
+
+impl Condition for NotInCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(!self.1.contains(&*self.0.eval(row)?, row)?)
+    }
+}
+
+/// Checks if the value is within the bounds, inclusive.
+#[derive(Debug)]
+pub struct BetweenCondition(pub Expr, pub Expr, pub Expr);
+
+impl Condition for BetweenCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        let value = self.0.eval(row)?;
+        Ok(
+            value.compare(&*self.1.eval(row)?).is_ge()
+                && value.compare(&*self.2.eval(row)?).is_le(),
+        )
+    }
+}
+
+/// Checks if the value is an empty string. The value should exist.
+#[derive(Debug)]
+pub struct IsEmptyCondition(pub Expr);
+
+impl Condition for IsEmptyCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(self.0.eval_text(row)?.is_empty())
+    }
+}
+
+#[derive(Debug)]
+pub struct IsNotEmptyCondition(pub Expr);
+
+impl Condition for IsNotEmptyCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(!self.0.eval_text(row)?.is_empty())
+    }
+}
+
+/// Checks if the value references a column, that the row doesn't have.
+#[derive(Debug)]
+pub struct IsMissingCondition(pub Expr);
+
+impl Condition for IsMissingCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(self.0.eval_text(row).is_err())
+    }
+}
+
+#[derive(Debug)]
+pub struct IsNotMissingCondition(pub Expr);
+
+impl Condition for IsNotMissingCondition {
+    fn check(&self, row: &Row) -> CheckResult {
+        Ok(self.0.eval_text(row).is_ok())
     }
 }
 
@@ -47,7 +308,7 @@ impl Condition for EqCondition {
 pub struct TrueCondition;
 
 impl Condition for TrueCondition {
-    fn check(&self, _row: &Vec<String>) -> bool {
-        true
+    fn check(&self, _row: &Row) -> CheckResult {
+        Ok(true)
     }
 }
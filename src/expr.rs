@@ -0,0 +1,103 @@
+use crate::{
+    function::Function,
+    query::ColumnOutOfRangeError,
+    row::Row,
+    value::{BinaryOp, Value},
+};
+use std::borrow::Cow;
+
+/// An expression, that is evaluated for each row.
+#[derive(Debug)]
+pub enum Expr {
+    Column(usize),
+    /// A constant and its text, as it was written in a query.
+    Const {
+        text: String,
+        value: Value,
+        /// `true` for string literals, like `"1.1"`, rather than numbers
+        quoted: bool,
+    },
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+    /// Result of an aggregate function of a grouped row, by its id.
+    Aggregate(usize),
+}
+
+impl Expr {
+    pub fn new_const(text: String) -> Self {
+        let value = Value::parse(&text);
+        Expr::Const {
+            text,
+            value,
+            quoted: false,
+        }
+    }
+
+    pub fn new_str(text: String) -> Self {
+        let value = Value::parse(&text);
+        Expr::Const {
+            text,
+            value,
+            quoted: true,
+        }
+    }
+
+    pub fn is_str(&self) -> bool {
+        matches!(self, Expr::Const { quoted: true, .. })
+    }
+
+    /// Returns `true`, if the expression doesn't depend on a row.
+    pub fn is_const(&self) -> bool {
+        match self {
+            Expr::Column(_) | Expr::Aggregate(_) => false,
+            Expr::Const { .. } => true,
+            Expr::Neg(expr) => expr.is_const(),
+            Expr::Binary(_, lhs, rhs) => lhs.is_const() && rhs.is_const(),
+            Expr::Call(_, args) => args.iter().all(Expr::is_const),
+        }
+    }
+
+    pub fn eval<'a>(&'a self, row: &'a Row) -> Result<Cow<'a, Value>, ColumnOutOfRangeError> {
+        Ok(match self {
+            Expr::Column(col_id) => Cow::Borrowed(
+                row.value(*col_id)
+                    .ok_or_else(|| ColumnOutOfRangeError::new(row, *col_id))?,
+            ),
+            Expr::Const { value, .. } => Cow::Borrowed(value),
+            Expr::Neg(expr) => Cow::Owned(expr.eval(row)?.neg()),
+            Expr::Binary(op, lhs, rhs) => Cow::Owned(op.apply(&*lhs.eval(row)?, &*rhs.eval(row)?)),
+            Expr::Call(function, args) => Cow::Owned(match call(function, args, row)? {
+                Value::Str(s) => Value::parse(&s),
+                value => value,
+            }),
+            Expr::Aggregate(aggregate_id) => Cow::Borrowed(
+                row.aggregate(*aggregate_id)
+                    .expect("aggregates are only used for grouped rows"),
+            ),
+        })
+    }
+
+    /// Evaluates the expression as a text. Unlike `eval(..).to_string()`, keeps cells, constants
+    /// and function results as is: e.g. `007` is not converted to `7`.
+    pub fn eval_text<'a>(&'a self, row: &'a Row) -> Result<Cow<'a, str>, ColumnOutOfRangeError> {
+        Ok(match self {
+            Expr::Column(col_id) => Cow::Borrowed(
+                row.get(*col_id)
+                    .ok_or_else(|| ColumnOutOfRangeError::new(row, *col_id))?,
+            ),
+            Expr::Const { text, .. } => Cow::Borrowed(text),
+            Expr::Call(function, args) => Cow::Owned(call(function, args, row)?.to_string()),
+            _ => Cow::Owned(self.eval(row)?.to_string()),
+        })
+    }
+}
+
+fn call(function: &Function, args: &[Expr], row: &Row) -> Result<Value, ColumnOutOfRangeError> {
+    let args = args
+        .iter()
+        .map(|arg| arg.eval_text(row))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((function.call)(&args))
+}
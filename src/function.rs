@@ -0,0 +1,150 @@
+use crate::value::Value;
+use itertools::Itertools;
+use std::{borrow::Cow, collections::HashMap};
+
+/// A built-in function, callable from a query, e.g. `lower(NAME)`.
+///
+/// Arguments are passed as text, exactly as they are written in the input or in the query. String
+/// results are parsed into typed values lazily, when they are compared or computed with.
+#[derive(Debug, Clone, Copy)]
+pub struct Function {
+    pub name: &'static str,
+    pub min_args: usize,
+    /// `None` means any number of arguments.
+    pub max_args: Option<usize>,
+    pub call: fn(&[Cow<str>]) -> Value,
+}
+
+impl Function {
+    pub fn accepts_arg_count(&self, count: usize) -> bool {
+        count >= self.min_args && self.max_args.is_none_or(|max_args| count <= max_args)
+    }
+}
+
+#[derive(Debug)]
+pub struct FunctionRegistry {
+    functions: HashMap<&'static str, Function>,
+}
+
+impl FunctionRegistry {
+    pub fn builtin() -> Self {
+        let functions = [
+            Function {
+                name: "lower",
+                min_args: 1,
+                max_args: Some(1),
+                call: |args| Value::Str(args[0].to_lowercase()),
+            },
+            Function {
+                name: "upper",
+                min_args: 1,
+                max_args: Some(1),
+                call: |args| Value::Str(args[0].to_uppercase()),
+            },
+            Function {
+                name: "len",
+                min_args: 1,
+                max_args: Some(1),
+                call: |args| Value::Int(args[0].chars().count() as i64),
+            },
+            Function {
+                name: "trim",
+                min_args: 1,
+                max_args: Some(1),
+                call: |args| Value::Str(args[0].trim().to_string()),
+            },
+            Function {
+                name: "substr",
+                min_args: 2,
+                max_args: Some(3),
+                call: substr,
+            },
+            Function {
+                name: "replace",
+                min_args: 3,
+                max_args: Some(3),
+                call: |args| Value::Str(args[0].replace(args[1].as_ref(), &args[2])),
+            },
+            Function {
+                name: "split_part",
+                min_args: 3,
+                max_args: Some(3),
+                call: split_part,
+            },
+            Function {
+                name: "concat",
+                min_args: 1,
+                max_args: None,
+                call: |args| Value::Str(args.iter().join("")),
+            },
+            Function {
+                name: "round",
+                min_args: 1,
+                max_args: Some(2),
+                call: round,
+            },
+        ];
+
+        Self {
+            functions: functions.into_iter().map(|f| (f.name, f)).collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Function> {
+        self.functions.get(name).copied()
+    }
+}
+
+/// Parses a 1-based position or a length.
+fn parse_count(s: &str) -> Option<usize> {
+    s.trim().parse().ok()
+}
+
+/// `substr(s, start[, len])`: `len` characters, starting from the `start`-th one (1-based).
+fn substr(args: &[Cow<str>]) -> Value {
+    let Some(start) = parse_count(&args[1]) else {
+        return Value::Empty;
+    };
+    let len = match args.get(2) {
+        Some(len) => match parse_count(len) {
+            Some(len) => len,
+            None => return Value::Empty,
+        },
+        None => usize::MAX,
+    };
+
+    Value::Str(args[0].chars().skip(start.max(1) - 1).take(len).collect())
+}
+
+/// `split_part(s, sep, n)`: `n`-th (1-based) part of `s`, split by `sep`.
+fn split_part(args: &[Cow<str>]) -> Value {
+    match parse_count(&args[2]) {
+        Some(n) if n > 0 && !args[1].is_empty() => match args[0].split(args[1].as_ref()).nth(n - 1)
+        {
+            Some(part) => Value::Str(part.to_string()),
+            None => Value::Empty,
+        },
+        _ => Value::Empty,
+    }
+}
+
+/// `round(x[, digits])`
+fn round(args: &[Cow<str>]) -> Value {
+    let digits = match args.get(1) {
+        Some(digits) => match digits.trim().parse::<i32>() {
+            Ok(digits) => digits,
+            Err(_) => return Value::Empty,
+        },
+        None => 0,
+    };
+
+    match Value::parse(&args[0]) {
+        Value::Int(i) => Value::Int(i),
+        Value::Float(x) if digits <= 0 => Value::Int(x.round() as i64),
+        Value::Float(x) => {
+            let multiplier = 10f64.powi(digits);
+            Value::Float((x * multiplier).round() / multiplier)
+        }
+        _ => Value::Empty,
+    }
+}
@@ -1,22 +1,34 @@
+use aggregate::Aggregator;
 use clap::Parser;
+use output::Output;
 use parser::QueryParseAns;
 use printer::Printer;
+use query::BadRowPolicy;
 use splitter::Splitter;
 use std::{
     error::Error,
     io::{stdin, BufRead},
+    process::ExitCode,
 };
 
+mod aggregate;
 mod condition;
+mod expr;
+mod function;
+mod output;
 mod parser;
 mod printer;
 mod query;
+mod row;
+mod sorter;
 mod splitter;
+mod value;
 
 #[derive(Debug, thiserror::Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum MainError {
     #[error("couldn't parse query string")]
-    QueryParseError(#[from] parser::ParseError),
+    QueryParseError(#[from] parser::QueryParseError),
 
     #[error("couldn't process a line")]
     LineProcessError(#[from] query::LineProcessError),
@@ -25,11 +37,24 @@ pub enum MainError {
     ReadError(#[from] std::io::Error),
 }
 
+impl MainError {
+    /// `1` is reserved for `--fail-if-empty`, like in `grep`.
+    fn exit_code(&self) -> u8 {
+        match self {
+            MainError::QueryParseError(_) => 2,
+            MainError::LineProcessError(_) => 3,
+            MainError::ReadError(_) => 4,
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(about, long_about = None, disable_help_flag = true, disable_help_subcommand = true)]
 struct Cli {
     /// Query string
     // TODO: describe format
+    // Queries like `-1` (the last column) should not be treated as flags
+    #[arg(allow_hyphen_values = true)]
     query_string: String,
 
     // Make help flag long-only
@@ -40,6 +65,10 @@ struct Cli {
     #[arg(long)]
     no_pretty: bool,
 
+    /// Align numeric columns (numbers, sizes and durations) of a pretty table to the right.
+    #[arg(long)]
+    align_numbers: bool,
+
     /// Print a header.
     #[arg(long, short = 'h')]
     print_header: bool,
@@ -52,61 +81,114 @@ struct Cli {
     /// Sepparate each row by whitespace symbols. May be more effective than default smart separation.
     #[arg(long)]
     ws_sep: bool,
+
+    /// Print sizes (like `1.5K` or `2GiB`) as a number of bytes.
+    #[arg(long, short = 'b')]
+    bytes: bool,
+
+    /// What to do with rows, that lack columns, used in the query.
+    #[arg(long, value_enum, default_value_t = BadRowPolicy::Error)]
+    on_bad_row: BadRowPolicy,
+
+    /// Match column names case-insensitively.
+    #[arg(long)]
+    ignore_case: bool,
+
+    /// Allow to abbreviate column names to unique prefixes, like `COM` for `COMMAND`.
+    #[arg(long)]
+    abbrev: bool,
+
+    /// Exit with code 1, if no rows are output, like `grep` does.
+    #[arg(long)]
+    fail_if_empty: bool,
     // // TODO: enable this
     // /// Don't trim strings
     // #[arg(long, short = 't')]
     // no_trim: bool,
 }
 
-fn real_main() -> Result<(), MainError> {
-    let cli = Cli::parse();
-
-    let lines = stdin().lock().lines().map(|l| l.unwrap() /* FIXME: */);
-    let mut splitter = Splitter::from_cli_and_lines(&cli, lines).peekable();
+/// Returns the number of output rows, not counting the header.
+fn real_main(cli: &Cli) -> Result<usize, MainError> {
+    let lines = stdin().lock().lines();
+    let mut splitter = Splitter::from_cli_and_lines(cli, lines)?.peekable();
 
     let first_row = match splitter.peek() {
-        Some(l) => l,
+        Some(Ok(row)) => row,
+        Some(Err(_)) => return Err(splitter.next().unwrap().unwrap_err().into()),
         None => {
             eprintln!("warning: empty input");
-            return Ok(());
+            return Ok(0);
         }
     };
 
     let QueryParseAns {
         query,
         is_header_required,
-    } = parser::parse(&cli.query_string, &first_row)?;
+    } = parser::parse(cli, first_row.cells())?;
 
     let has_header = cli.has_header || cli.print_header || is_header_required;
     let print_header = cli.print_header;
 
-    let mut printer = Printer::new(&cli, has_header, print_header);
+    let mut printer = Printer::new(cli, has_header, print_header);
 
     if has_header {
-        printer.push_header(query.process_line_no_check(&splitter.next().unwrap())?);
+        printer.push_header(query.header(&splitter.next().unwrap()?)?);
     }
+    let mut output = Output::new(&query, printer);
+    let mut aggregator = query.group_by.as_ref().map(Aggregator::new);
+
     for row in splitter {
-        if let Some(row) = query.process_line(&row)? {
-            printer.push_row(row);
+        if output.is_full() {
+            break;
+        }
+        let row = row?;
+
+        match aggregator.as_mut() {
+            Some(aggregator) => {
+                if let Err(e) = aggregator.push(&row) {
+                    query.skip_bad_row(e)?;
+                }
+            }
+            None => output.push(&row)?,
         }
     }
 
-    printer.finish();
+    if let Some(aggregator) = aggregator {
+        for row in aggregator.finish() {
+            output.push(&row)?;
+        }
+    }
 
-    Ok(())
+    let printed_count = output.finish()?;
+
+    let skipped_count = query.skipped_count.get();
+    if skipped_count > 0 {
+        eprintln!("warning: skipped bad rows: {skipped_count}");
+    }
+
+    Ok(printed_count)
 }
 
 // Print error as Display, rather than as Debug
 fn print_error(e: &dyn Error) {
-    print!("{}", e);
+    eprint!("{}", e);
     if let Some(next_e) = e.source() {
-        print!(": ");
+        eprint!(": ");
         print_error(next_e);
     }
 }
 
-fn main() {
-    if let Err(e) = real_main() {
-        print_error(&e);
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match real_main(&cli) {
+        Ok(0) if cli.fail_if_empty => ExitCode::FAILURE,
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprint!("error: ");
+            print_error(&e);
+            eprintln!();
+            ExitCode::from(e.exit_code())
+        }
     }
 }
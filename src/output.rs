@@ -0,0 +1,125 @@
+use crate::{
+    printer::Printer,
+    query::{Dedup, LineProcessError, Query},
+    row::Row,
+    sorter::Sorter,
+};
+use std::collections::HashSet;
+
+/// Processes rows with a query and passes the result to a printer, removing duplicates, sorting
+/// it and applying `offset` and `limit`.
+///
+/// `distinct` is applied before sorting, `uniq` after it, like in `sort | uniq`.
+pub struct Output<'a> {
+    query: &'a Query,
+    printer: Printer,
+    sorter: Option<Sorter<'a>>,
+    /// Rows, that were already output. Only for `distinct`.
+    seen: HashSet<Vec<String>>,
+    /// The last row and the number of its consecutive repeats. Only for `uniq`.
+    pending: Option<(Vec<String>, usize)>,
+    /// Number of output rows, before `offset` and `limit` are applied
+    matched_count: usize,
+    /// Number of rows, passed to the printer
+    printed_count: usize,
+}
+
+impl<'a> Output<'a> {
+    /// The header, if any, should already be pushed to the printer. It is not sorted.
+    pub fn new(query: &'a Query, printer: Printer) -> Self {
+        // Consecutive duplicates are only known after sorting, so all rows are needed for `uniq`
+        let capacity = match query.dedup {
+            Dedup::Uniq { .. } => None,
+            Dedup::None | Dedup::Distinct => query.end(),
+        };
+        let sorter = (!query.sort_keys.is_empty()).then(|| Sorter::new(&query.sort_keys, capacity));
+
+        Self {
+            query,
+            printer,
+            sorter,
+            seen: HashSet::new(),
+            pending: None,
+            matched_count: 0,
+            printed_count: 0,
+        }
+    }
+
+    /// Returns `true`, if no more rows will be printed, so the rest of the input may be skipped.
+    pub fn is_full(&self) -> bool {
+        self.sorter.is_none()
+            && self
+                .query
+                .end()
+                .is_some_and(|end| self.matched_count >= end)
+    }
+
+    pub fn push(&mut self, row: &Row) -> Result<(), LineProcessError> {
+        let Some(output) = self.query.process_line(row)? else {
+            return Ok(());
+        };
+
+        if self.query.dedup == Dedup::Distinct && !self.seen.insert(output.clone()) {
+            return Ok(());
+        }
+
+        match self.sorter.as_mut() {
+            Some(sorter) => match sorter.push(row, output) {
+                Ok(()) => Ok(()),
+                Err(e) => self.query.skip_bad_row(e),
+            },
+            None => {
+                self.push_sorted(output);
+                Ok(())
+            }
+        }
+    }
+
+    /// Pushes rows in the output order.
+    fn push_sorted(&mut self, output: Vec<String>) {
+        if let Dedup::Uniq { .. } = self.query.dedup {
+            match self.pending.as_mut() {
+                Some((last, count)) if *last == output => *count += 1,
+                _ => {
+                    if let Some(pending) = self.pending.replace((output, 1)) {
+                        self.push_pending(pending);
+                    }
+                }
+            }
+        } else {
+            self.push_unique(output);
+        }
+    }
+
+    fn push_pending(&mut self, (mut output, count): (Vec<String>, usize)) {
+        if let Dedup::Uniq { count: true } = self.query.dedup {
+            output.insert(0, count.to_string());
+        }
+        self.push_unique(output);
+    }
+
+    fn push_unique(&mut self, output: Vec<String>) {
+        if self.matched_count >= self.query.offset && !self.is_full() {
+            self.printer.push_row(output);
+            self.printed_count += 1;
+        }
+        self.matched_count += 1;
+    }
+
+    /// Returns the number of printed rows.
+    pub fn finish(mut self) -> Result<usize, LineProcessError> {
+        if let Some(sorter) = self.sorter.take() {
+            for row in sorter.finish() {
+                self.push_sorted(row);
+            }
+        }
+
+        if let Some(pending) = self.pending.take() {
+            self.push_pending(pending);
+        }
+
+        self.printer.finish();
+
+        Ok(self.printed_count)
+    }
+}
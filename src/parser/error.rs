@@ -1,27 +1,319 @@
 use super::Rule;
+use itertools::Itertools;
+use pest::error::{ErrorVariant, InputLocation};
+use std::borrow::Cow;
 
+/// A [`ParseError`] together with the query, so that it's displayed with the wrong part of the
+/// query marked.
 #[derive(Debug, thiserror::Error)]
+#[error("{error}\n{}", render_span(query, error.span()))]
+pub struct QueryParseError {
+    pub(super) query: String,
+    pub(super) error: ParseError,
+}
+
+/// Byte range of a query, an error refers to.
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl From<pest::Span<'_>> for Span {
+    fn from(span: pest::Span<'_>) -> Self {
+        Self {
+            start: span.start(),
+            end: span.end(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum ParseError {
-    #[error("pest parser error")]
-    PestError(#[from] pest::error::Error<Rule>),
+    #[error("{}", describe_pest_error(.0))]
+    PestError(#[from] Box<pest::error::Error<Rule>>),
 
     #[error(transparent)]
     SameColumnNamesError(#[from] SameColumnNamesError),
 
     #[error(transparent)]
     ColumnNotFoundError(#[from] ColumnNotFoundError),
+
+    #[error(transparent)]
+    AmbiguousColumnNameError(#[from] AmbiguousColumnNameError),
+
+    #[error(transparent)]
+    InvalidRegexError(#[from] InvalidRegexError),
+
+    #[error(transparent)]
+    InvalidEscapeError(#[from] InvalidEscapeError),
+
+    #[error(transparent)]
+    UnknownFunctionError(#[from] UnknownFunctionError),
+
+    #[error(transparent)]
+    WrongArgumentCountError(#[from] WrongArgumentCountError),
+
+    #[error(transparent)]
+    AggregateNotAllowedError(#[from] AggregateNotAllowedError),
+
+    #[error(transparent)]
+    InvalidColumnNumberError(#[from] InvalidColumnNumberError),
+
+    #[error(transparent)]
+    CountTooLargeError(#[from] CountTooLargeError),
+}
+
+impl From<pest::error::Error<Rule>> for ParseError {
+    fn from(e: pest::error::Error<Rule>) -> Self {
+        Box::new(e).into()
+    }
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::PestError(e) => match e.location {
+                InputLocation::Pos(pos) => Span {
+                    start: pos,
+                    end: pos,
+                },
+                InputLocation::Span((start, end)) => Span { start, end },
+            },
+            ParseError::SameColumnNamesError(e) => e.span,
+            ParseError::ColumnNotFoundError(e) => e.span,
+            ParseError::AmbiguousColumnNameError(e) => e.span,
+            ParseError::InvalidRegexError(e) => e.span,
+            ParseError::InvalidEscapeError(e) => e.span,
+            ParseError::UnknownFunctionError(e) => e.span,
+            ParseError::WrongArgumentCountError(e) => e.span,
+            ParseError::AggregateNotAllowedError(e) => e.span,
+            ParseError::InvalidColumnNumberError(e) => e.span,
+            ParseError::CountTooLargeError(e) => e.span,
+        }
+    }
+}
+
+/// Describes a syntax error in terms of tokens, rather than grammar rules.
+fn describe_pest_error(e: &pest::error::Error<Rule>) -> String {
+    let describe = |rules: &[Rule]| {
+        let descriptions = rules.iter().map(|rule| describe_rule(*rule)).unique();
+        let descriptions = descriptions.collect::<Vec<_>>();
+        match &descriptions[..] {
+            [] => String::new(),
+            [description] => description.to_string(),
+            [init @ .., last] => format!("{} or {last}", init.join(", ")),
+        }
+    };
+
+    match &e.variant {
+        ErrorVariant::ParsingError {
+            positives,
+            negatives,
+        } => match (positives.is_empty(), negatives.is_empty()) {
+            (false, true) => format!("syntax error: expected {}", describe(positives)),
+            (true, false) => format!("syntax error: unexpected {}", describe(negatives)),
+            (false, false) => format!(
+                "syntax error: unexpected {}, expected {}",
+                describe(negatives),
+                describe(positives)
+            ),
+            (true, true) => "syntax error".to_string(),
+        },
+        ErrorVariant::CustomError { message } => format!("syntax error: {message}"),
+    }
+}
+
+fn describe_rule(rule: Rule) -> Cow<'static, str> {
+    Cow::Borrowed(match rule {
+        Rule::EOI => "end of query",
+
+        Rule::arith_op_add
+        | Rule::arith_op_sub
+        | Rule::arith_op_mul
+        | Rule::arith_op_div
+        | Rule::arith_op_rem
+        | Rule::arith_op_neg => "arithmetic operator",
+
+        Rule::cond_op_eq
+        | Rule::cond_op_ne
+        | Rule::cond_op_le
+        | Rule::cond_op_lt
+        | Rule::cond_op_ge
+        | Rule::cond_op_gt
+        | Rule::cond_op_eq_i
+        | Rule::cond_op_ne_i
+        | Rule::cond_op_contains
+        | Rule::cond_op_icontains
+        | Rule::cond_op_startswith
+        | Rule::cond_op_istartswith
+        | Rule::cond_op_endswith
+        | Rule::cond_op_iendswith
+        | Rule::cond_match_op_yes
+        | Rule::cond_match_op_not
+        | Rule::cond_match_op_yes_i
+        | Rule::cond_match_op_not_i
+        | Rule::cond_in_op_yes
+        | Rule::cond_in_op_not
+        | Rule::cond_is_empty
+        | Rule::cond_is_not_empty
+        | Rule::cond_is_missing
+        | Rule::cond_is_not_missing
+        | Rule::kw_between => "comparison operator",
+        Rule::cond_expr_op_or => "`or`",
+        Rule::cond_expr_op_and => "`and`",
+        Rule::cond_expr_op_not => "`not`",
+
+        Rule::kw_distinct => "`distinct`",
+        Rule::kw_uniq => "`uniq`",
+        Rule::kw_count => "`count`",
+        Rule::kw_and => "`and`",
+        Rule::kw_if => "`if`",
+        Rule::kw_as => "`as`",
+        Rule::kw_except => "`except`",
+        Rule::kw_group => "`group`",
+        Rule::kw_having => "`having`",
+        Rule::kw_sort => "`sort`",
+        Rule::kw_by => "`by`",
+        Rule::kw_limit => "`limit`",
+        Rule::kw_offset => "`offset`",
+        Rule::sort_mode_numeric => "`numeric`",
+        Rule::sort_mode_natural => "`natural`",
+        Rule::sort_mode_lex => "`lex`",
+        Rule::sort_order_asc => "`asc`",
+        Rule::sort_order_desc => "`desc`",
+
+        Rule::column
+        | Rule::column_name
+        | Rule::column_name_quoted
+        | Rule::column_number
+        | Rule::column_number_neg
+        | Rule::column_range_bound => "column",
+        Rule::column_range => "range of columns",
+        Rule::number | Rule::natural_number | Rule::count => "number",
+        Rule::string | Rule::string_double | Rule::string_single | Rule::string_raw => "string",
+        Rule::projection | Rule::projection_expr | Rule::arith_expr | Rule::sort_key => {
+            "expression"
+        }
+        Rule::cond | Rule::cond_expr => "condition",
+        Rule::func_args => "`(`",
+
+        _ => return Cow::Owned(format!("{rule:?}").replace('_', " ")),
+    })
+}
+
+/// Renders the line of the query with a span, marking the span with carets.
+fn render_span(query: &str, span: Span) -> String {
+    let line_start = query[..span.start].rfind('\n').map_or(0, |pos| pos + 1);
+    let line_end = query[span.start..]
+        .find('\n')
+        .map_or(query.len(), |pos| span.start + pos);
+    let line_num = query[..span.start].matches('\n').count() + 1;
+
+    let offset = query[line_start..span.start].chars().count();
+    let width = query[span.start..span.end.min(line_end)]
+        .chars()
+        .count()
+        .max(1);
+    let gutter = " ".repeat(line_num.to_string().len());
+
+    format!(
+        "{gutter} |\n{line_num} | {}\n{gutter} | {}{}",
+        &query[line_start..line_end],
+        " ".repeat(offset),
+        "^".repeat(width)
+    )
 }
 
 #[derive(Debug, thiserror::Error)]
 #[error("columns {} and {} has the same name: {}", first_column_id + 1, second_column_id + 1, column_name)]
 pub struct SameColumnNamesError {
+    /// The column reference, that needed the header
+    pub(super) span: Span,
     pub(super) first_column_id: usize,
     pub(super) second_column_id: usize,
     pub(super) column_name: String,
 }
 
 #[derive(Debug, thiserror::Error)]
-#[error("column with name {column_name} not found")]
+#[error(
+    "column with name {column_name} not found.{} Available columns: {}",
+    fmt_suggestions(suggestions),
+    available_columns.join(", ")
+)]
 pub struct ColumnNotFoundError {
+    pub(super) span: Span,
     pub(super) column_name: String,
+    /// Similar column names, the closest first
+    pub(super) suggestions: Vec<String>,
+    pub(super) available_columns: Vec<String>,
+}
+
+fn fmt_suggestions(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(" Did you mean {}?", suggestions.join(" or "))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("column name {column_name} is ambiguous, it matches: {}", candidates.join(", "))]
+pub struct AmbiguousColumnNameError {
+    pub(super) span: Span,
+    pub(super) column_name: String,
+    pub(super) candidates: Vec<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid regular expression {pattern:?}: {source}")]
+pub struct InvalidRegexError {
+    pub(super) span: Span,
+    pub(super) pattern: String,
+    pub(super) source: regex::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid escape sequence {escape:?} in string {string:?}")]
+pub struct InvalidEscapeError {
+    pub(super) span: Span,
+    pub(super) escape: String,
+    pub(super) string: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown function {name}")]
+pub struct UnknownFunctionError {
+    pub(super) span: Span,
+    pub(super) name: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("function {name} doesn't accept {arg_count} arguments")]
+pub struct WrongArgumentCountError {
+    pub(super) span: Span,
+    pub(super) name: String,
+    pub(super) arg_count: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("aggregate function {name} can't be used in `if`, `group by` or inside another aggregate")]
+pub struct AggregateNotAllowedError {
+    pub(super) span: Span,
+    pub(super) name: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid column number {number}: columns are numbered from 1")]
+pub struct InvalidColumnNumberError {
+    pub(super) span: Span,
+    pub(super) number: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("number {count} is too large for `limit` or `offset`")]
+pub struct CountTooLargeError {
+    pub(super) span: Span,
+    pub(super) count: String,
 }
@@ -1,13 +1,14 @@
-pub use error::ParseError;
+pub use error::{ParseError, QueryParseError};
 
-use crate::query::Query;
+use crate::{aggregate::Aggregate, function::FunctionRegistry, query::Query, Cli};
 use error::*;
+use itertools::Itertools;
 use pest::{
     pratt_parser::{Assoc, Op, PrattParser},
     Parser,
 };
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::{hash_map::Entry, HashMap},
 };
 
@@ -24,9 +25,14 @@ pub struct QueryParseAns {
     pub query: Query,
 }
 
-pub fn parse(query_string: &str, first_row: &Vec<String>) -> Result<QueryParseAns, ParseError> {
-    let parser = QueryParser::from_first_row(first_row);
-    let query = parser.parse(query_string)?;
+pub fn parse(cli: &Cli, first_row: &[String]) -> Result<QueryParseAns, QueryParseError> {
+    let parser = QueryParser::from_cli_and_first_row(cli, first_row);
+    let query = parser
+        .parse(&cli.query_string)
+        .map_err(|error| QueryParseError {
+            query: cli.query_string.clone(),
+            error,
+        })?;
     let is_header_required = parser.header.borrow().is_some();
 
     Ok(QueryParseAns {
@@ -36,20 +42,41 @@ pub fn parse(query_string: &str, first_row: &Vec<String>) -> Result<QueryParseAn
 }
 
 struct QueryParser<'a> {
+    cli: &'a Cli,
     header: RefCell<Option<HashMap<String /* col_name */, usize /* col_id */>>>,
-    first_row: &'a Vec<String>,
+    first_row: &'a [String],
     pratt: PrattParser<Rule>,
+    arith_pratt: PrattParser<Rule>,
+    functions: FunctionRegistry,
+    /// Aggregate function calls, met in the query
+    aggregates: RefCell<Vec<Aggregate>>,
+    /// Aggregates are only allowed in expressions, that are evaluated for groups
+    aggregates_allowed: Cell<bool>,
 }
 
 impl<'a> QueryParser<'a> {
-    fn from_first_row(first_row: &'a Vec<String>) -> QueryParser {
+    fn from_cli_and_first_row(cli: &'a Cli, first_row: &'a [String]) -> QueryParser<'a> {
         let pratt = PrattParser::new()
             .op(Op::infix(Rule::cond_expr_op_or, Assoc::Left))
-            .op(Op::infix(Rule::cond_expr_op_and, Assoc::Left));
+            .op(Op::infix(Rule::cond_expr_op_and, Assoc::Left))
+            .op(Op::prefix(Rule::cond_expr_op_not));
+
+        let arith_pratt = PrattParser::new()
+            .op(Op::infix(Rule::arith_op_add, Assoc::Left)
+                | Op::infix(Rule::arith_op_sub, Assoc::Left))
+            .op(Op::infix(Rule::arith_op_mul, Assoc::Left)
+                | Op::infix(Rule::arith_op_div, Assoc::Left)
+                | Op::infix(Rule::arith_op_rem, Assoc::Left))
+            .op(Op::prefix(Rule::arith_op_neg));
 
         QueryParser {
+            cli,
             header: RefCell::new(None),
             pratt,
+            arith_pratt,
+            functions: FunctionRegistry::builtin(),
+            aggregates: RefCell::new(Vec::new()),
+            aggregates_allowed: Cell::new(true),
             first_row,
         }
     }
@@ -62,13 +89,15 @@ impl<'a> QueryParser<'a> {
         self.parse_query(query)
     }
 
-    fn init_header(&self) -> Result<(), SameColumnNamesError> {
+    /// `span` is of the column name, that needs the header.
+    fn init_header(&self, span: Span) -> Result<(), SameColumnNamesError> {
         let mut header = HashMap::new();
 
         for (col_id, col_name) in self.first_row.iter().enumerate() {
             match header.entry(col_name.to_string()) {
                 Entry::Occupied(entry) => {
                     return Err(SameColumnNamesError {
+                        span,
                         first_column_id: *entry.get(),
                         second_column_id: col_id,
                         column_name: col_name.clone(),
@@ -85,22 +114,113 @@ impl<'a> QueryParser<'a> {
         Ok(())
     }
 
-    fn get_column_number(&self, column_name: &str) -> Result<usize, ParseError> {
+    /// An exact match is preferred. If there is no such one, a column may be matched
+    /// case-insensitively or by a prefix of its name, if it's enabled.
+    fn get_column_number(&self, column_name: &str, span: Span) -> Result<usize, ParseError> {
         if self.header.borrow().is_none() {
-            self.init_header()?;
+            self.init_header(span)?;
+        }
+
+        if let Some(col_id) = self.header.borrow().as_ref().unwrap().get(column_name) {
+            return Ok(*col_id);
         }
 
-        self.header
-            .borrow_mut()
-            .as_ref()
-            .unwrap()
-            .get(column_name)
-            .ok_or(
-                ColumnNotFoundError {
-                    column_name: column_name.to_string(),
+        let normalize = |name: &str| {
+            if self.cli.ignore_case {
+                name.to_lowercase()
+            } else {
+                name.to_string()
+            }
+        };
+        let name = normalize(column_name);
+
+        // Whole names are preferred to prefixes
+        for by_prefix in [false, true] {
+            let is_enabled = if by_prefix {
+                self.cli.abbrev
+            } else {
+                self.cli.ignore_case
+            };
+            if !is_enabled {
+                continue;
+            }
+
+            let candidates = self
+                .first_row
+                .iter()
+                .enumerate()
+                .filter(|(_, header_name)| {
+                    let header_name = normalize(header_name);
+                    if by_prefix {
+                        header_name.starts_with(&name)
+                    } else {
+                        header_name == name
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            match candidates[..] {
+                [] => continue,
+                [(col_id, _)] => return Ok(col_id),
+                _ => {
+                    return Err(AmbiguousColumnNameError {
+                        span,
+                        column_name: column_name.to_string(),
+                        candidates: candidates
+                            .into_iter()
+                            .map(|(_, name)| name.clone())
+                            .collect(),
+                    }
+                    .into())
                 }
-                .into(),
-            )
-            .copied()
+            }
+        }
+
+        Err(ColumnNotFoundError {
+            span,
+            column_name: column_name.to_string(),
+            suggestions: self.suggest_column_names(column_name),
+            available_columns: self.first_row.to_vec(),
+        }
+        .into())
+    }
+
+    /// Returns up to three column names, that are close to the given one or start with it.
+    fn suggest_column_names(&self, column_name: &str) -> Vec<String> {
+        let column_name = column_name.to_lowercase();
+        let max_distance = (column_name.chars().count() / 3).max(1);
+
+        self.first_row
+            .iter()
+            .map(|header_name| {
+                let distance = edit_distance(&column_name, &header_name.to_lowercase());
+                (distance, header_name)
+            })
+            .filter(|(distance, header_name)| {
+                *distance <= max_distance || header_name.to_lowercase().starts_with(&column_name)
+            })
+            .sorted_by_key(|(distance, _)| *distance)
+            .take(3)
+            .map(|(_, header_name)| header_name.clone())
+            .collect()
+    }
+}
+
+/// Levenshtein distance between strings.
+fn edit_distance(lhs: &str, rhs: &str) -> usize {
+    let rhs = rhs.chars().collect::<Vec<_>>();
+    let mut prev_row = (0..=rhs.len()).collect::<Vec<_>>();
+
+    for (i, l) in lhs.chars().enumerate() {
+        let mut row = vec![i + 1; rhs.len() + 1];
+        for (j, r) in rhs.iter().enumerate() {
+            let replace_cost = if l == *r { 0 } else { 1 };
+            row[j + 1] = (prev_row[j] + replace_cost)
+                .min(prev_row[j + 1] + 1)
+                .min(row[j] + 1);
+        }
+        prev_row = row;
     }
+
+    prev_row[rhs.len()]
 }
@@ -1,10 +1,28 @@
-use super::{ParseError, QueryParser, Rule};
+use super::{
+    error::{
+        AggregateNotAllowedError, CountTooLargeError, InvalidColumnNumberError, InvalidEscapeError,
+        InvalidRegexError, Span, UnknownFunctionError, WrongArgumentCountError,
+    },
+    ParseError, QueryParser, Rule,
+};
 use crate::{
-    condition::{Condition, ConditionExpr, ConditionOperand, EqCondition, TrueCondition},
-    query::Query,
+    aggregate::{Aggregate, AggregateKind, GroupBy},
+    condition::{
+        BetweenCondition, Condition, ConditionExpr, ContainsCondition, EndsWithCondition,
+        EqCondition, EqIgnoreCaseCondition, GeCondition, GtCondition, InCondition,
+        IsEmptyCondition, IsMissingCondition, IsNotEmptyCondition, IsNotMissingCondition,
+        LeCondition, LtCondition, MatchCondition, NeCondition, NeIgnoreCaseCondition,
+        NotInCondition, NotMatchCondition, StartsWithCondition, TrueCondition, ValueSet,
+    },
+    expr::Expr,
+    query::{ColumnIndex, ColumnRange, Dedup, Projection, Query},
+    sorter::{SortKey, SortMode},
+    value::{self, BinaryOp, Value},
 };
 use itertools::Itertools;
-use pest::iterators::Pair;
+use pest::iterators::{Pair, Pairs};
+use regex::{Regex, RegexBuilder};
+use std::cell::Cell;
 
 type ParseResult<T> = Result<T, ParseError>;
 
@@ -13,50 +31,419 @@ impl<'a> QueryParser<'a> {
         assert!(query.as_rule() == Rule::query);
         let pairs = query.into_inner();
 
-        let mut column_ids = Vec::new();
+        let mut dedup = Dedup::None;
+        let mut projections = Vec::new();
         let mut cond_expr = ConditionExpr::Condition(Box::new(TrueCondition));
+        let mut group_keys = None;
+        let mut having = None;
+        let mut sort_keys = Vec::new();
+        let mut limit = None;
+        let mut offset = 0;
 
         for pair in pairs {
             match pair.as_rule() {
                 Rule::EOI => {
                     break;
                 }
-                Rule::column => {
-                    column_ids.push(self.parse_column(pair)?);
+                Rule::dedup => {
+                    let mut pairs = pair.into_inner();
+                    dedup = match pairs.next().unwrap().as_rule() {
+                        Rule::kw_distinct => Dedup::Distinct,
+                        Rule::kw_uniq => Dedup::Uniq {
+                            count: pairs.next().is_some(),
+                        },
+                        _ => unreachable!(),
+                    };
+                }
+                Rule::projection => {
+                    projections.extend(self.parse_projection(pair)?);
                 }
                 Rule::_if => {
-                    let inner = pair.into_inner().next().unwrap();
-                    cond_expr = self.parse_cond_expr(inner)?;
+                    let inner = pair.into_inner().nth(1).unwrap();
+                    cond_expr = self.without_aggregates(|| self.parse_cond_expr(inner, false))?;
+                }
+                Rule::group_by => {
+                    let mut keys = Vec::new();
+                    for pair in pair.into_inner() {
+                        match pair.as_rule() {
+                            Rule::projection_expr => keys.push(
+                                self.without_aggregates(|| self.parse_projection_expr(pair))?,
+                            ),
+                            Rule::having => {
+                                let inner = pair.into_inner().nth(1).unwrap();
+                                having = Some(self.parse_cond_expr(inner, true)?);
+                            }
+                            _ => {}
+                        }
+                    }
+                    group_keys = Some(keys);
+                }
+                Rule::sort_by => {
+                    sort_keys = pair
+                        .into_inner()
+                        .filter(|pair| pair.as_rule() == Rule::sort_key)
+                        .map(|sort_key| self.parse_sort_key(sort_key))
+                        .collect::<ParseResult<_>>()?;
+                }
+                Rule::limit => {
+                    limit = Some(parse_count(pair)?);
+                }
+                Rule::offset => {
+                    offset = parse_count(pair)?;
                 }
                 _ => unreachable!(),
             }
         }
 
+        if projections.is_empty() {
+            projections.push(Projection::All { except: Vec::new() });
+        }
+
+        // Aggregates without `group by` make a single group of all rows
+        let aggregates = self.aggregates.take();
+        let group_by = if group_keys.is_some() || !aggregates.is_empty() {
+            let group_by = GroupBy {
+                keys: group_keys.unwrap_or_default(),
+                aggregates,
+                filter: cond_expr,
+            };
+            cond_expr = having.unwrap_or(ConditionExpr::Condition(Box::new(TrueCondition)));
+            Some(group_by)
+        } else {
+            None
+        };
+
         Ok(Query {
-            column_ids,
+            projections,
             cond_expr,
+            group_by,
+            dedup,
+            sort_keys,
+            limit,
+            offset,
+            sizes_as_bytes: self.cli.bytes,
+            on_bad_row: self.cli.on_bad_row,
+            skipped_count: Cell::new(0),
+        })
+    }
+
+    /// Returns several projections for ranges of columns.
+    fn parse_projection(&self, projection: Pair<Rule>) -> ParseResult<Vec<Projection>> {
+        assert!(projection.as_rule() == Rule::projection);
+        let mut pairs = projection.into_inner();
+
+        let projection_expr = pairs.next().unwrap();
+        match projection_expr.as_rule() {
+            Rule::column_range => return self.parse_column_range(projection_expr),
+            Rule::all_columns => return Ok(vec![self.parse_all_columns(projection_expr)?]),
+            _ => {}
+        }
+
+        let name = projection_expr.as_str().to_string();
+        let expr = self.parse_projection_expr(projection_expr)?;
+
+        let alias = match pairs.next() {
+            Some(alias) => Some(self.parse_alias(alias)?),
+            None => None,
+        };
+
+        Ok(vec![Projection::Expr { expr, name, alias }])
+    }
+
+    fn parse_projection_expr(&self, projection_expr: Pair<Rule>) -> ParseResult<Expr> {
+        assert!(projection_expr.as_rule() == Rule::projection_expr);
+
+        // `-1` is parsed as a column range, where it's allowed. `-0` and the rest are errors, rather
+        // than negated constants.
+        let text = projection_expr.as_str();
+        if text.strip_prefix('-').is_some_and(is_number) {
+            return Err(InvalidColumnNumberError {
+                span: projection_expr.as_span().into(),
+                number: text.to_string(),
+            }
+            .into());
+        }
+
+        self.parse_operand(projection_expr.into_inner().next().unwrap())
+    }
+
+    /// Parses an expression, that is a whole projection or the left operand of an `if` condition.
+    /// A single number is a column number there, rather than a constant.
+    fn parse_operand(&self, arith_expr: Pair<Rule>) -> ParseResult<Expr> {
+        // Inside a condition, the span includes whitespace before the operator
+        let text = arith_expr.as_str().trim_end();
+        if is_number(text) {
+            let span = Span {
+                start: arith_expr.as_span().start(),
+                end: arith_expr.as_span().start() + text.len(),
+            };
+            return Ok(Expr::Column(parse_column_number(text, span)?));
+        }
+
+        self.parse_arith_expr(arith_expr)
+    }
+
+    fn parse_column_range(&self, column_range: Pair<Rule>) -> ParseResult<Vec<Projection>> {
+        assert!(column_range.as_rule() == Rule::column_range);
+        let text = column_range.as_str();
+        let pairs = column_range.into_inner().collect_vec();
+
+        let (start, end) = match pairs[..] {
+            // `-1`
+            [ref index] if index.as_rule() == Rule::column_number_neg => {
+                let index = self.parse_column_index(index.clone())?;
+                (index, index)
+            }
+            // `..2`
+            [ref end] if text.starts_with("..") => (
+                ColumnIndex::FromStart(0),
+                self.parse_column_range_bound(end.clone())?,
+            ),
+            // `3..`
+            [ref start] => (
+                self.parse_column_range_bound(start.clone())?,
+                ColumnIndex::FromEnd(0),
+            ),
+            // `3..7`
+            [ref start, ref end] => (
+                self.parse_column_range_bound(start.clone())?,
+                self.parse_column_range_bound(end.clone())?,
+            ),
+            _ => unreachable!(),
+        };
+
+        // Ranges, that don't depend on a row length, are expanded right away
+        Ok(match (start, end) {
+            (ColumnIndex::FromStart(start), ColumnIndex::FromStart(end)) => {
+                let col_ids = if start <= end {
+                    (start..=end).collect_vec()
+                } else {
+                    (end..=start).rev().collect_vec()
+                };
+                col_ids
+                    .into_iter()
+                    .map(|col_id| Projection::Expr {
+                        expr: Expr::Column(col_id),
+                        name: text.to_string(),
+                        alias: None,
+                    })
+                    .collect()
+            }
+            (start, end) => vec![Projection::Columns(ColumnRange { start, end })],
         })
     }
 
+    fn parse_all_columns(&self, all_columns: Pair<Rule>) -> ParseResult<Projection> {
+        assert!(all_columns.as_rule() == Rule::all_columns);
+
+        let except = all_columns
+            .into_inner()
+            .filter(|pair| pair.as_rule() == Rule::column_range_bound)
+            .map(|bound| self.parse_column_range_bound(bound))
+            .collect::<ParseResult<Vec<_>>>()?;
+
+        Ok(Projection::All { except })
+    }
+
+    fn parse_column_range_bound(&self, bound: Pair<Rule>) -> ParseResult<ColumnIndex> {
+        assert!(bound.as_rule() == Rule::column_range_bound);
+        self.parse_column_index(bound.into_inner().next().unwrap())
+    }
+
+    fn parse_column_index(&self, index: Pair<Rule>) -> ParseResult<ColumnIndex> {
+        Ok(match index.as_rule() {
+            Rule::column_number_neg => ColumnIndex::FromEnd(parse_column_number(
+                &index.as_str()[1..],
+                index.as_span().into(),
+            )?),
+            Rule::column => ColumnIndex::FromStart(self.parse_column(index)?),
+            _ => unreachable!(),
+        })
+    }
+
+    fn parse_alias(&self, alias: Pair<Rule>) -> ParseResult<String> {
+        assert!(alias.as_rule() == Rule::projection_alias);
+        let inner = alias.into_inner().nth(1).unwrap();
+
+        Ok(match inner.as_rule() {
+            Rule::string => self.parse_str(inner, false)?,
+            Rule::column_name_quoted => unquote_column_name(inner.as_str()),
+            Rule::column_name => inner.as_str().to_string(),
+            _ => unreachable!(),
+        })
+    }
+
+    fn parse_arith_expr(&self, arith_expr: Pair<Rule>) -> ParseResult<Expr> {
+        assert!(arith_expr.as_rule() == Rule::arith_expr);
+        let pairs = arith_expr.into_inner();
+
+        self.arith_pratt
+            .map_primary(|primary| self.parse_arith_primary(primary))
+            .map_prefix(|op, rhs| {
+                let rhs = Box::new(rhs?);
+                Ok(match op.as_rule() {
+                    Rule::arith_op_neg => Expr::Neg(rhs),
+                    _ => unreachable!(),
+                })
+            })
+            .map_infix(|lhs, op, rhs| {
+                let lhs = Box::new(lhs?);
+                let rhs = Box::new(rhs?);
+                let op = match op.as_rule() {
+                    Rule::arith_op_add => BinaryOp::Add,
+                    Rule::arith_op_sub => BinaryOp::Sub,
+                    Rule::arith_op_mul => BinaryOp::Mul,
+                    Rule::arith_op_div => BinaryOp::Div,
+                    Rule::arith_op_rem => BinaryOp::Rem,
+                    _ => unreachable!(),
+                };
+                Ok(Expr::Binary(op, lhs, rhs))
+            })
+            .parse(pairs)
+    }
+
+    fn parse_arith_primary(&self, primary: Pair<Rule>) -> ParseResult<Expr> {
+        Ok(match primary.as_rule() {
+            Rule::arith_paren => self.parse_arith_expr(primary.into_inner().next().unwrap())?,
+            Rule::number => Expr::new_const(primary.as_str().to_string()),
+            Rule::string => Expr::new_str(self.parse_str(primary, false)?),
+            Rule::now if self.first_row.iter().any(|name| name == "now") => {
+                Expr::Column(self.get_column_number("now", primary.as_span().into())?)
+            }
+            Rule::now => {
+                let value = Value::DateTime(value::now());
+                Expr::Const {
+                    text: value.to_string(),
+                    value,
+                    quoted: false,
+                }
+            }
+            Rule::func_call => self.parse_func_call(primary)?,
+            Rule::column => Expr::Column(self.parse_column(primary)?),
+            _ => unreachable!(),
+        })
+    }
+
+    fn parse_func_call(&self, func_call: Pair<Rule>) -> ParseResult<Expr> {
+        assert!(func_call.as_rule() == Rule::func_call);
+        let span = func_call.as_span().into();
+        let mut pairs = func_call.into_inner();
+
+        let name = pairs.next().unwrap().as_str();
+        let args = pairs.next().unwrap().into_inner();
+
+        if let Some(kind) = AggregateKind::from_name(name) {
+            return self.parse_aggregate(kind, name, args, span);
+        }
+
+        let args = args
+            .map(|arg| self.parse_arith_expr(arg))
+            .collect::<ParseResult<Vec<_>>>()?;
+
+        let function = self
+            .functions
+            .get(name)
+            .ok_or_else(|| UnknownFunctionError {
+                span,
+                name: name.to_string(),
+            })?;
+
+        if !function.accepts_arg_count(args.len()) {
+            return Err(WrongArgumentCountError {
+                span,
+                name: name.to_string(),
+                arg_count: args.len(),
+            }
+            .into());
+        }
+
+        Ok(Expr::Call(function, args))
+    }
+
+    /// Returns a reference to the aggregate. Its arguments are evaluated for input rows.
+    fn parse_aggregate(
+        &self,
+        kind: AggregateKind,
+        name: &str,
+        args: Pairs<Rule>,
+        span: Span,
+    ) -> ParseResult<Expr> {
+        if !self.aggregates_allowed.get() {
+            return Err(AggregateNotAllowedError {
+                span,
+                name: name.to_string(),
+            }
+            .into());
+        }
+
+        let mut args = self.without_aggregates(|| {
+            args.map(|arg| self.parse_arith_expr(arg))
+                .collect::<ParseResult<Vec<_>>>()
+        })?;
+
+        if !kind.accepts_arg_count(args.len()) {
+            return Err(WrongArgumentCountError {
+                span,
+                name: name.to_string(),
+                arg_count: args.len(),
+            }
+            .into());
+        }
+
+        let mut aggregates = self.aggregates.borrow_mut();
+        aggregates.push(Aggregate {
+            kind,
+            arg: args.pop(),
+        });
+
+        Ok(Expr::Aggregate(aggregates.len() - 1))
+    }
+
+    /// Parses something, that is evaluated for input rows, rather than for groups.
+    fn without_aggregates<T>(&self, f: impl FnOnce() -> ParseResult<T>) -> ParseResult<T> {
+        let was_allowed = self.aggregates_allowed.replace(false);
+        let ans = f();
+        self.aggregates_allowed.set(was_allowed);
+        ans
+    }
+
     /// Returns col_id.
     fn parse_column(&self, column: Pair<Rule>) -> ParseResult<usize> {
         assert!(column.as_rule() == Rule::column);
 
         let pair = column.into_inner().next().unwrap();
+        let span = pair.as_span().into();
 
         Ok(match pair.as_rule() {
-            Rule::column_number => pair.as_str().parse::<usize>().unwrap() - 1,
-            Rule::column_name => self.get_column_number(pair.as_str())?,
+            Rule::column_number => {
+                parse_column_number(pair.as_str().trim_start_matches('$'), span)?
+            }
+            Rule::column_name => self.get_column_number(pair.as_str(), span)?,
+            Rule::column_name_quoted => {
+                self.get_column_number(&unquote_column_name(pair.as_str()), span)?
+            }
             _ => unreachable!(),
         })
     }
 
-    fn parse_cond_expr(&self, cond_expr: Pair<Rule>) -> ParseResult<ConditionExpr> {
+    /// `in_having` is `true` for conditions of `having`, which are checked for groups.
+    fn parse_cond_expr(
+        &self,
+        cond_expr: Pair<Rule>,
+        in_having: bool,
+    ) -> ParseResult<ConditionExpr> {
         assert!(cond_expr.as_rule() == Rule::cond_expr);
         let pairs = cond_expr.into_inner();
 
         self.pratt
-            .map_primary(|cond_atom| self.parse_cond_atom(cond_atom))
+            .map_primary(|cond_atom| self.parse_cond_atom(cond_atom, in_having))
+            .map_prefix(|op, rhs| {
+                let rhs = Box::new(rhs?);
+                Ok(match op.as_rule() {
+                    Rule::cond_expr_op_not => ConditionExpr::Not(rhs),
+                    _ => unreachable!(),
+                })
+            })
             .map_infix(|lhs, op, rhs| {
                 let lhs = Box::new(lhs?);
                 let rhs = Box::new(rhs?);
@@ -69,46 +456,279 @@ impl<'a> QueryParser<'a> {
             .parse(pairs)
     }
 
-    fn parse_cond_atom(&self, cond_atom: Pair<Rule>) -> ParseResult<ConditionExpr> {
+    fn parse_cond_atom(
+        &self,
+        cond_atom: Pair<Rule>,
+        in_having: bool,
+    ) -> ParseResult<ConditionExpr> {
         match cond_atom.as_rule() {
-            Rule::cond => Ok(ConditionExpr::Condition(self.parse_cond(cond_atom)?)),
-            Rule::cond_expr => self.parse_cond_expr(cond_atom),
+            Rule::cond => Ok(ConditionExpr::Condition(
+                self.parse_cond(cond_atom, in_having)?,
+            )),
+            Rule::cond_expr => self.parse_cond_expr(cond_atom, in_having),
             _ => unreachable!(),
         }
     }
 
-    fn parse_cond(&self, cond: Pair<Rule>) -> ParseResult<Box<dyn Condition>> {
+    fn parse_cond(&self, cond: Pair<Rule>, in_having: bool) -> ParseResult<Box<dyn Condition>> {
         assert!(cond.as_rule() == Rule::cond);
         let mut pairs = cond.into_inner();
 
-        let lhs = self.parse_cond_operand(pairs.next().unwrap())?;
+        // Only a single number on the left of an `if` condition is a column number: `2 = "y"`.
+        // Everywhere else numbers are values: `PORT > 100`, `having count() > 1`.
+        let lhs = pairs.next().unwrap();
+        let lhs = if in_having {
+            self.parse_arith_expr(lhs)?
+        } else {
+            self.parse_operand(lhs)?
+        };
         let op = pairs.next().unwrap();
-        let rhs = self.parse_cond_operand(pairs.next().unwrap())?;
+
+        if matches!(
+            op.as_rule(),
+            Rule::cond_match_op_yes
+                | Rule::cond_match_op_not
+                | Rule::cond_match_op_yes_i
+                | Rule::cond_match_op_not_i
+        ) {
+            let ignore_case = matches!(
+                op.as_rule(),
+                Rule::cond_match_op_yes_i | Rule::cond_match_op_not_i
+            );
+            let regex = self.parse_regex(pairs.next().unwrap(), ignore_case)?;
+            assert!(pairs.next().is_none());
+
+            return Ok(match op.as_rule() {
+                Rule::cond_match_op_yes | Rule::cond_match_op_yes_i => {
+                    Box::new(MatchCondition(lhs, regex))
+                }
+                Rule::cond_match_op_not | Rule::cond_match_op_not_i => {
+                    Box::new(NotMatchCondition(lhs, regex))
+                }
+                _ => unreachable!(),
+            });
+        }
+
+        match op.as_rule() {
+            Rule::cond_is_empty => return Ok(Box::new(IsEmptyCondition(lhs))),
+            Rule::cond_is_not_empty => return Ok(Box::new(IsNotEmptyCondition(lhs))),
+            Rule::cond_is_missing => return Ok(Box::new(IsMissingCondition(lhs))),
+            Rule::cond_is_not_missing => return Ok(Box::new(IsNotMissingCondition(lhs))),
+            _ => {}
+        }
+
+        if matches!(op.as_rule(), Rule::cond_in_op_yes | Rule::cond_in_op_not) {
+            let items = pairs
+                .map(|item| self.parse_arith_expr(item))
+                .collect::<ParseResult<Vec<_>>>()?;
+            let values = ValueSet::new(items);
+
+            return Ok(match op.as_rule() {
+                Rule::cond_in_op_yes => Box::new(InCondition(lhs, values)),
+                Rule::cond_in_op_not => Box::new(NotInCondition(lhs, values)),
+                _ => unreachable!(),
+            });
+        }
+
+        if op.as_rule() == Rule::kw_between {
+            let start = self.parse_arith_expr(pairs.next().unwrap())?;
+            let end = self.parse_arith_expr(pairs.nth(1).unwrap())?;
+            assert!(pairs.next().is_none());
+
+            return Ok(Box::new(BetweenCondition(lhs, start, end)));
+        }
+
+        let rhs = self.parse_arith_expr(pairs.next().unwrap())?;
         assert!(pairs.next().is_none());
 
         Ok(match op.as_rule() {
             Rule::cond_op_eq => Box::new(EqCondition(lhs, rhs)),
+            Rule::cond_op_ne => Box::new(NeCondition(lhs, rhs)),
+            Rule::cond_op_lt => Box::new(LtCondition(lhs, rhs)),
+            Rule::cond_op_le => Box::new(LeCondition(lhs, rhs)),
+            Rule::cond_op_gt => Box::new(GtCondition(lhs, rhs)),
+            Rule::cond_op_ge => Box::new(GeCondition(lhs, rhs)),
+            Rule::cond_op_eq_i => Box::new(EqIgnoreCaseCondition(lhs, rhs)),
+            Rule::cond_op_ne_i => Box::new(NeIgnoreCaseCondition(lhs, rhs)),
+            Rule::cond_op_contains | Rule::cond_op_icontains => Box::new(ContainsCondition {
+                haystack: lhs,
+                needle: rhs,
+                ignore_case: op.as_rule() == Rule::cond_op_icontains,
+            }),
+            Rule::cond_op_startswith | Rule::cond_op_istartswith => Box::new(StartsWithCondition {
+                haystack: lhs,
+                needle: rhs,
+                ignore_case: op.as_rule() == Rule::cond_op_istartswith,
+            }),
+            Rule::cond_op_endswith | Rule::cond_op_iendswith => Box::new(EndsWithCondition {
+                haystack: lhs,
+                needle: rhs,
+                ignore_case: op.as_rule() == Rule::cond_op_iendswith,
+            }),
             _ => unreachable!(),
         })
     }
 
-    fn parse_cond_operand(&self, cond_operand: Pair<Rule>) -> ParseResult<ConditionOperand> {
-        assert!(cond_operand.as_rule() == Rule::cond_operand);
-        let inner = cond_operand.into_inner().next().unwrap();
+    fn parse_sort_key(&self, sort_key: Pair<Rule>) -> ParseResult<SortKey> {
+        assert!(sort_key.as_rule() == Rule::sort_key);
+        let mut pairs = sort_key.into_inner();
+
+        let expr = self.parse_projection_expr(pairs.next().unwrap())?;
+        let mut mode = SortMode::Typed;
+        let mut descending = false;
+
+        for pair in pairs {
+            match pair.as_rule() {
+                Rule::sort_mode_numeric => mode = SortMode::Numeric,
+                Rule::sort_mode_natural => mode = SortMode::Natural,
+                Rule::sort_mode_lex => mode = SortMode::Lexicographic,
+                Rule::sort_order_asc => descending = false,
+                Rule::sort_order_desc => descending = true,
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(SortKey {
+            expr,
+            mode,
+            descending,
+        })
+    }
+
+    fn parse_regex(&self, s: Pair<Rule>, ignore_case: bool) -> ParseResult<Regex> {
+        let span = s.as_span().into();
+        let pattern = self.parse_str(s, true)?;
+        RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|source| {
+                InvalidRegexError {
+                    span,
+                    pattern,
+                    source,
+                }
+                .into()
+            })
+    }
+
+    /// With `keep_unknown_escapes`, escapes like `\d` are kept as is, rather than rejected: this
+    /// is for regexes.
+    fn parse_str(&self, s: Pair<Rule>, keep_unknown_escapes: bool) -> ParseResult<String> {
+        assert!(s.as_rule() == Rule::string);
+        let inner = s.into_inner().next().unwrap();
+        let text = inner.as_str();
 
         Ok(match inner.as_rule() {
-            Rule::column => ConditionOperand::ColumnId(self.parse_column(inner)?),
-            Rule::string => ConditionOperand::Const(self.parse_str(inner)?),
+            Rule::string_raw => {
+                let hash_count = text[1..].find('"').unwrap();
+                text[hash_count + 2..text.len() - hash_count - 1].to_string()
+            }
+            Rule::string_double | Rule::string_single => {
+                let offset = inner.as_span().start() + 1;
+                unescape(&text[1..text.len() - 1], offset, keep_unknown_escapes)?
+            }
             _ => unreachable!(),
         })
     }
+}
 
-    fn parse_str(&self, s: Pair<Rule>) -> ParseResult<String> {
-        assert!(s.as_rule() == Rule::string);
-        let mut chars = s.as_str().chars().collect_vec();
-        assert!(chars.len() >= 2 && chars[0] == '"' && *chars.last().unwrap() == '"');
-        chars.pop();
-        chars.remove(0);
-        Ok(chars.iter().join(""))
+/// Parses a number of a `limit` or an `offset` clause.
+fn parse_count(pair: Pair<Rule>) -> ParseResult<usize> {
+    let count = pair.into_inner().nth(1).unwrap();
+    count.as_str().parse().map_err(|_| {
+        CountTooLargeError {
+            span: count.as_span().into(),
+            count: count.as_str().to_string(),
+        }
+        .into()
+    })
+}
+
+/// Returns `true` for an unsigned integer, like `7` or `007`.
+fn is_number(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Returns col_id of a 1-based column number.
+fn parse_column_number(number: &str, span: Span) -> ParseResult<usize> {
+    match number.parse::<usize>() {
+        Ok(col_num) if col_num > 0 => Ok(col_num - 1),
+        _ => Err(InvalidColumnNumberError {
+            span,
+            number: number.to_string(),
+        }
+        .into()),
     }
 }
+
+/// Removes backticks around a quoted column name.
+fn unquote_column_name(name: &str) -> String {
+    name[1..name.len() - 1].replace("``", "`")
+}
+
+/// Replaces escape sequences (e.g. `\"` or `\u{1F600}`) with the characters they denote.
+/// `offset` is the position of the string in the query.
+fn unescape(
+    s: &str,
+    offset: usize,
+    keep_unknown_escapes: bool,
+) -> Result<String, InvalidEscapeError> {
+    let mut ans = String::with_capacity(s.len());
+    let mut chars = s.char_indices();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            ans.push(c);
+            continue;
+        }
+
+        let err = |escape: &str| InvalidEscapeError {
+            span: Span {
+                start: offset + start,
+                end: offset + start + escape.len(),
+            },
+            escape: escape.to_string(),
+            string: s.to_string(),
+        };
+
+        let (pos, c) = chars.next().ok_or_else(|| err("\\"))?;
+        let c = match c {
+            '"' => '"',
+            '\'' => '\'',
+            '\\' => '\\',
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            'u' => {
+                let rest = &s[pos + 1..];
+                let escape_len = match rest.find('}') {
+                    Some(end) if rest.starts_with('{') => end + 1,
+                    _ => return Err(err("\\u")),
+                };
+                let escape = &rest[..escape_len];
+                let code = &escape[1..escape_len - 1];
+
+                let c = if (1..=6).contains(&code.len()) {
+                    u32::from_str_radix(code, 16).ok().and_then(char::from_u32)
+                } else {
+                    None
+                };
+                let c = c.ok_or_else(|| err(&format!("\\u{escape}")))?;
+
+                for _ in 0..escape_len {
+                    chars.next();
+                }
+                c
+            }
+            c if keep_unknown_escapes => {
+                ans.push('\\');
+                c
+            }
+            c => return Err(err(&format!("\\{c}"))),
+        };
+        ans.push(c);
+    }
+
+    Ok(ans)
+}
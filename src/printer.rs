@@ -1,4 +1,5 @@
-use crate::Cli;
+use crate::{value::ValueType, Cli};
+use itertools::Itertools;
 
 pub struct Printer {
     data: Vec<Vec<String>>,
@@ -6,6 +7,7 @@ pub struct Printer {
     style: PrinterStyle,
     has_header: bool,
     print_header: bool,
+    align_numbers: bool,
 }
 
 impl Printer {
@@ -16,6 +18,7 @@ impl Printer {
             style: PrinterStyle::from_cli(cli),
             has_header,
             print_header,
+            align_numbers: cli.align_numbers,
         }
     }
 
@@ -28,6 +31,7 @@ impl Printer {
     }
 
     fn push_row_header(&mut self, row: Vec<String>, is_header: bool) {
+        // Rows may have different lengths, if the input is ragged
         match self.column_count {
             // First run
             None => {
@@ -37,7 +41,7 @@ impl Printer {
 
             // Not first run
             Some(column_count) => {
-                assert!(column_count == row.len());
+                self.column_count = Some(column_count.max(row.len()));
             }
         }
 
@@ -47,13 +51,7 @@ impl Printer {
 
         match self.style {
             PrinterStyle::Simple => {
-                for (col_id, item) in row.iter().enumerate() {
-                    print!("{item}");
-                    if col_id + 1 != self.column_count.unwrap() {
-                        print!(" ");
-                    }
-                }
-                println!("")
+                println!("{}", row.iter().join(" "));
             }
             PrinterStyle::Table => {
                 self.data.push(row);
@@ -72,27 +70,56 @@ impl Printer {
             return;
         }
 
-        let mut column_widths = vec![0usize; self.column_count.unwrap()];
-        let mut rows = self.data.iter();
-        if self.has_header && !self.print_header {
-            rows.next();
-        }
-        for row in rows {
+        let column_count = self.column_count.unwrap();
+
+        let mut column_widths = vec![0usize; column_count];
+        for row in self.data.iter() {
             for (col_id, item) in row.iter().enumerate() {
                 column_widths[col_id] = column_widths[col_id].max(item.len())
             }
         }
 
+        // Numeric columns are aligned to the right, if it's enabled
+        let data_rows = if self.has_header && self.print_header {
+            &self.data[1..]
+        } else {
+            &self.data[..]
+        };
+        let is_column_numeric = (0..column_count)
+            .map(|col_id| {
+                self.align_numbers
+                    && ValueType::infer(
+                        data_rows
+                            .iter()
+                            .filter_map(|row| row.get(col_id))
+                            .map(|s| s.as_str()),
+                    )
+                    .is_numeric()
+            })
+            .collect_vec();
+
         for row in self.data.iter() {
+            // Trailing empty cells, e.g. padded ones, are not printed, to avoid trailing spaces
+            let row = &row[..row
+                .iter()
+                .rposition(|item| !item.is_empty())
+                .map_or(0, |i| i + 1)];
+
             for (col_id, item) in row.iter().enumerate() {
-                if col_id + 1 != self.column_count.unwrap() {
-                    print!("{item:<width$}", width = column_widths[col_id]);
-                    print!(" ");
+                let width = column_widths[col_id];
+                if is_column_numeric[col_id] {
+                    print!("{item:>width$}");
+                } else if col_id + 1 != row.len() {
+                    print!("{item:<width$}");
                 } else {
                     print!("{item}");
                 }
+
+                if col_id + 1 != row.len() {
+                    print!(" ");
+                }
             }
-            println!("");
+            println!();
         }
     }
 }
@@ -1,4 +1,9 @@
-use crate::condition::ConditionExpr;
+use crate::{
+    aggregate::GroupBy, condition::ConditionExpr, expr::Expr, row::Row, sorter::SortKey,
+    value::Value,
+};
+use itertools::Itertools;
+use std::cell::Cell;
 
 #[derive(Debug, thiserror::Error)]
 pub enum LineProcessError {
@@ -8,55 +13,312 @@ pub enum LineProcessError {
 
 #[derive(Debug, thiserror::Error)]
 #[error(
-    "cannot get column number {col_num} as there are only {col_num} columns. \
-Line: \"{line}\""
+    "cannot get column number {col_num} as there are only {col_count} columns{}. \
+Line: \"{line}\"",
+    line_num.map(|line_num| format!(" in line {line_num}")).unwrap_or_default()
 )]
 pub struct ColumnOutOfRangeError {
     line: String,
-    col_num: usize,
+    /// Negative for columns, counted from the end
+    col_num: isize,
     col_count: usize,
-    // TODO: line_num
+    line_num: Option<usize>,
+}
+
+impl ColumnOutOfRangeError {
+    pub fn new(row: &Row, col_id: usize) -> Self {
+        Self::from_index(row, ColumnIndex::FromStart(col_id))
+    }
+
+    fn from_index(row: &Row, index: ColumnIndex) -> Self {
+        let col_num = match index {
+            ColumnIndex::FromStart(col_id) => col_id as isize + 1,
+            ColumnIndex::FromEnd(offset) => -(offset as isize) - 1,
+        };
+
+        Self {
+            line: format!("{:?}", row.cells()),
+            col_num,
+            col_count: row.len(),
+            line_num: row.line_num(),
+        }
+    }
+}
+
+/// What to do with a row, that lacks a column, used in a query.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum BadRowPolicy {
+    /// Stop with an error
+    Error,
+    /// Skip the row
+    Skip,
+    /// Output missing cells as empty. Rows, that lack a column to filter, sort or group by, are
+    /// skipped.
+    Pad,
+    /// Skip the row with a warning
+    Warn,
+}
+
+/// An output column or several ones.
+#[derive(Debug)]
+pub enum Projection {
+    Expr {
+        expr: Expr,
+        /// Text of the expression. Is used as a header of computed columns.
+        name: String,
+        /// Header, given with `as`.
+        alias: Option<String>,
+    },
+    /// A range of columns, that is resolved for each row separately, as row lengths may vary.
+    Columns(ColumnRange),
+    /// All columns of a row, except the given ones.
+    All { except: Vec<ColumnIndex> },
+}
+
+/// Position of a column, counted either from the start or from the end of a row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnIndex {
+    /// 0-based column id
+    FromStart(usize),
+    /// `FromEnd(0)` is the last column
+    FromEnd(usize),
+}
+
+impl ColumnIndex {
+    fn resolve(self, row_len: usize) -> Option<usize> {
+        match self {
+            ColumnIndex::FromStart(col_id) => (col_id < row_len).then_some(col_id),
+            ColumnIndex::FromEnd(offset) => row_len.checked_sub(offset + 1),
+        }
+    }
+}
+
+/// An inclusive range of columns. If `start` is after `end`, columns are taken in the reverse
+/// order.
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnRange {
+    pub start: ColumnIndex,
+    pub end: ColumnIndex,
+}
+
+impl Projection {
+    /// Returns ids of input columns for projections, that are sets of columns.
+    fn resolve_columns(&self, row: &Row) -> Result<Vec<usize>, ColumnOutOfRangeError> {
+        Ok(match self {
+            Projection::Expr { .. } => unreachable!(),
+            Projection::Columns(range) => range.resolve(row)?,
+            Projection::All { except } => {
+                let except = except
+                    .iter()
+                    .filter_map(|index| index.resolve(row.len()))
+                    .collect_vec();
+                (0..row.len())
+                    .filter(|col_id| !except.contains(col_id))
+                    .collect()
+            }
+        })
+    }
+}
+
+impl ColumnRange {
+    /// Returns ids of columns in the range. Parts of the range, that don't fit the row, are
+    /// skipped. A range of a single column is an exception: the column should exist.
+    fn resolve(&self, row: &Row) -> Result<Vec<usize>, ColumnOutOfRangeError> {
+        if self.start == self.end {
+            return match self.start.resolve(row.len()) {
+                Some(col_id) => Ok(vec![col_id]),
+                None => Err(ColumnOutOfRangeError::from_index(row, self.start)),
+            };
+        }
+
+        // Positions are shifted by one, so that the column before the first one is representable
+        let pos = |index| match index {
+            ColumnIndex::FromStart(col_id) => col_id as isize + 1,
+            ColumnIndex::FromEnd(offset) => row.len() as isize - offset as isize,
+        };
+        let (start, end) = (pos(self.start), pos(self.end));
+        let row_range = 1..=row.len() as isize;
+
+        let ans = if start <= end {
+            (start..=end)
+                .filter(|pos| row_range.contains(pos))
+                .collect_vec()
+        } else {
+            (end..=start)
+                .rev()
+                .filter(|pos| row_range.contains(pos))
+                .collect_vec()
+        };
+
+        Ok(ans.into_iter().map(|pos| pos as usize - 1).collect())
+    }
+}
+
+/// A column of the output, resolved for a row.
+struct OutputColumn<'a> {
+    /// `None`, if the header is the one of the input column
+    header: Option<&'a str>,
+    source: ColumnSource<'a>,
+}
+
+enum ColumnSource<'a> {
+    /// A cell of the input column. Is an error, if the row is too short to have it.
+    Cell(Result<usize, ColumnOutOfRangeError>),
+    Expr(&'a Expr),
+}
+
+/// Removal of duplicate output rows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dedup {
+    None,
+    /// `distinct`: all duplicates are removed
+    Distinct,
+    /// `uniq`: only consecutive duplicates are removed. With `count`, the number of rows in each
+    /// run is printed in the first column.
+    Uniq {
+        count: bool,
+    },
 }
 
 #[derive(Debug)]
 pub struct Query {
-    pub column_ids: Vec<usize>,
+    pub projections: Vec<Projection>,
+    /// Is checked for output rows: it is the `having` condition, if rows are grouped, and the
+    /// `if` one otherwise.
     pub cond_expr: ConditionExpr,
+    pub group_by: Option<GroupBy>,
+    pub dedup: Dedup,
+    /// Empty, if the output is not sorted
+    pub sort_keys: Vec<SortKey>,
+    /// Maximum number of output rows
+    pub limit: Option<usize>,
+    /// Number of output rows to skip
+    pub offset: usize,
+    pub sizes_as_bytes: bool,
+    pub on_bad_row: BadRowPolicy,
+    /// Number of rows, skipped by `on_bad_row`
+    pub skipped_count: Cell<usize>,
 }
 
 impl Query {
-    pub fn process_line(&self, row: &Vec<String>) -> Result<Option<Vec<String>>, LineProcessError> {
-        if self.cond_expr.check(row) {
-            Ok(Some(self.get_columns(row)?))
-        } else {
-            Ok(None)
+    /// Number of matched rows, after which the rest are not needed.
+    pub fn end(&self) -> Option<usize> {
+        self.limit.map(|limit| self.offset.saturating_add(limit))
+    }
+
+    pub fn process_line(&self, row: &Row) -> Result<Option<Vec<String>>, LineProcessError> {
+        if !self.cond_expr.check(row) {
+            return Ok(None);
+        }
+
+        match self.get_columns(row, self.on_bad_row == BadRowPolicy::Pad) {
+            Ok(columns) => Ok(Some(columns)),
+            Err(e) => self.skip_bad_row(e).map(|()| None),
+        }
+    }
+
+    /// Applies `on_bad_row` to a row, that caused the error. Returns the error, unless the row
+    /// should be skipped.
+    pub fn skip_bad_row(&self, e: LineProcessError) -> Result<(), LineProcessError> {
+        match self.on_bad_row {
+            BadRowPolicy::Error => return Err(e),
+            BadRowPolicy::Skip | BadRowPolicy::Pad => {}
+            BadRowPolicy::Warn => {
+                let LineProcessError::ColumnOutOfRangeError(e) = &e;
+                eprintln!("warning: skipping a row: {e}");
+            }
+        }
+
+        self.skipped_count.set(self.skipped_count.get() + 1);
+        Ok(())
+    }
+
+    /// Returns the header of the output, given the header row of the input.
+    pub fn header(&self, row: &Row) -> Result<Vec<String>, LineProcessError> {
+        let mut ans = Vec::new();
+
+        if let Dedup::Uniq { count: true } = self.dedup {
+            ans.push("count".to_string());
         }
+
+        for column in self.output_columns(row) {
+            ans.push(match (column.header, column.source) {
+                (Some(header), _) => header.to_string(),
+                (None, ColumnSource::Cell(col_id)) => Self::get_cell(row, col_id?)?.to_string(),
+                (None, ColumnSource::Expr(_)) => unreachable!(),
+            });
+        }
+
+        Ok(ans)
     }
 
-    pub fn process_line_no_check(
-        &self,
-        row: &Vec<String>,
-    ) -> Result<Vec<String>, LineProcessError> {
-        Ok(self.get_columns(row)?)
+    /// With `pad`, missing cells are returned as empty strings.
+    fn get_columns(&self, row: &Row, pad: bool) -> Result<Vec<String>, LineProcessError> {
+        let mut ans = Vec::new();
+
+        for column in self.output_columns(row) {
+            let cell = match column.source {
+                ColumnSource::Cell(col_id) => {
+                    col_id.and_then(|col_id| self.get_column(row, col_id))
+                }
+                ColumnSource::Expr(expr) => expr.eval_text(row).map(|text| text.into_owned()),
+            };
+            ans.push(match cell {
+                Err(_) if pad => String::new(),
+                cell => cell?,
+            });
+        }
+
+        Ok(ans)
     }
 
-    fn get_columns(&self, row: &Vec<String>) -> Result<Vec<String>, LineProcessError> {
+    /// Resolves projections into separate output columns for the row.
+    fn output_columns(&self, row: &Row) -> Vec<OutputColumn<'_>> {
         let mut ans = Vec::new();
 
-        for col_id in self.column_ids.iter() {
-            match row.get(*col_id) {
-                Some(s) => ans.push(s.to_string()),
-                None => {
-                    return Err(ColumnOutOfRangeError {
-                        line: format!("{row:?}"),
-                        col_num: col_id + 1,
-                        col_count: row.len(),
+        for projection in self.projections.iter() {
+            match projection {
+                Projection::Expr { expr, name, alias } => ans.push(OutputColumn {
+                    header: match (alias, expr) {
+                        (Some(alias), _) => Some(alias),
+                        (None, Expr::Column(_)) => None,
+                        (None, _) => Some(name),
+                    },
+                    source: match expr {
+                        Expr::Column(col_id) => ColumnSource::Cell(Ok(*col_id)),
+                        expr => ColumnSource::Expr(expr),
+                    },
+                }),
+                Projection::Columns(_) | Projection::All { .. } => {
+                    match projection.resolve_columns(row) {
+                        Ok(col_ids) => ans.extend(col_ids.into_iter().map(|col_id| OutputColumn {
+                            header: None,
+                            source: ColumnSource::Cell(Ok(col_id)),
+                        })),
+                        // A single missing column of a range
+                        Err(e) => ans.push(OutputColumn {
+                            header: None,
+                            source: ColumnSource::Cell(Err(e)),
+                        }),
                     }
-                    .into());
                 }
             }
         }
 
-        Ok(ans)
+        ans
+    }
+
+    /// Cells are printed as is, if possible.
+    fn get_column(&self, row: &Row, col_id: usize) -> Result<String, ColumnOutOfRangeError> {
+        let cell = Self::get_cell(row, col_id)?;
+        Ok(match row.value(col_id) {
+            Some(Value::Size(bytes)) if self.sizes_as_bytes => bytes.to_string(),
+            _ => cell.to_string(),
+        })
+    }
+
+    fn get_cell(row: &Row, col_id: usize) -> Result<&str, ColumnOutOfRangeError> {
+        row.get(col_id)
+            .ok_or_else(|| ColumnOutOfRangeError::new(row, col_id))
     }
 }
@@ -0,0 +1,61 @@
+use crate::value::Value;
+use std::cell::OnceCell;
+
+/// A split line of an input. Cells are parsed into [`Value`]s lazily, on the first access.
+///
+/// A row, that represents a group of rows, also holds results of aggregate functions.
+#[derive(Debug, Clone)]
+pub struct Row {
+    cells: Vec<String>,
+    values: Vec<OnceCell<Value>>,
+    aggregates: Vec<Value>,
+    /// 1-based number of the input line, if the row comes from one
+    line_num: Option<usize>,
+}
+
+impl Row {
+    pub fn new(cells: Vec<String>) -> Self {
+        let values = cells.iter().map(|_| OnceCell::new()).collect();
+        Self {
+            cells,
+            values,
+            aggregates: Vec::new(),
+            line_num: None,
+        }
+    }
+
+    pub fn with_line_num(self, line_num: Option<usize>) -> Self {
+        Self { line_num, ..self }
+    }
+
+    pub fn with_aggregates(self, aggregates: Vec<Value>) -> Self {
+        Self { aggregates, ..self }
+    }
+
+    pub fn cells(&self) -> &[String] {
+        &self.cells
+    }
+
+    pub fn line_num(&self) -> Option<usize> {
+        self.line_num
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn get(&self, col_id: usize) -> Option<&str> {
+        self.cells.get(col_id).map(|s| s.as_str())
+    }
+
+    /// Each cell is parsed on its own, so conditions and sorting don't depend on other cells of
+    /// the column: in a column of numbers, `n/a` is still a string.
+    pub fn value(&self, col_id: usize) -> Option<&Value> {
+        let cell = self.cells.get(col_id)?;
+        Some(self.values[col_id].get_or_init(|| Value::parse(cell)))
+    }
+
+    pub fn aggregate(&self, aggregate_id: usize) -> Option<&Value> {
+        self.aggregates.get(aggregate_id)
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{expr::Expr, query::LineProcessError, row::Row, value::Value};
+use std::{cmp::Ordering, collections::BinaryHeap, iter::Peekable, str::Chars};
+
+/// An expression to sort by, given with `sort by`.
+#[derive(Debug)]
+pub struct SortKey {
+    pub expr: Expr,
+    pub mode: SortMode,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortMode {
+    /// Numbers, sizes, durations and dates are compared as values, everything else as strings.
+    Typed,
+    /// Values are compared as numbers. Non-numeric values go before numeric ones.
+    Numeric,
+    /// Strings are compared with respect to numbers inside of them: `file2` < `file10`.
+    Natural,
+    Lexicographic,
+}
+
+impl SortKey {
+    fn eval(&self, row: &Row) -> Result<Value, LineProcessError> {
+        Ok(match self.mode {
+            SortMode::Typed | SortMode::Numeric => self.expr.eval(row)?.into_owned(),
+            SortMode::Natural | SortMode::Lexicographic => {
+                Value::Str(self.expr.eval_text(row)?.into_owned())
+            }
+        })
+    }
+
+    fn compare(&self, lhs: &Value, rhs: &Value) -> Ordering {
+        let ans = match self.mode {
+            SortMode::Typed => typed_cmp(lhs, rhs),
+            SortMode::Numeric => match (as_number(lhs), as_number(rhs)) {
+                (Some(lhs), Some(rhs)) => lhs.total_cmp(&rhs),
+                (None, Some(_)) => Ordering::Less,
+                (Some(_), None) => Ordering::Greater,
+                (None, None) => typed_cmp(lhs, rhs),
+            },
+            SortMode::Natural => natural_cmp(&lhs.to_string(), &rhs.to_string()),
+            SortMode::Lexicographic => lhs.to_string().cmp(&rhs.to_string()),
+        };
+
+        if self.descending {
+            ans.reverse()
+        } else {
+            ans
+        }
+    }
+}
+
+/// A total order for values of mixed types: empty values go first, then numbers and sizes,
+/// durations, dates and strings. [`Value::compare`] falls back to comparing strings for
+/// different types, which isn't transitive: `10` < `9a` < `9`.
+fn typed_cmp(lhs: &Value, rhs: &Value) -> Ordering {
+    fn category(value: &Value) -> u8 {
+        match value {
+            Value::Empty => 0,
+            Value::Int(_) | Value::Float(_) | Value::Size(_) => 1,
+            Value::Duration(_) => 2,
+            Value::DateTime(_) => 3,
+            Value::Str(_) => 4,
+        }
+    }
+
+    category(lhs)
+        .cmp(&category(rhs))
+        // Values of the same category are compared numerically or as strings
+        .then_with(|| lhs.compare(rhs))
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Duration(seconds) | Value::DateTime(seconds) => Some(*seconds as f64),
+        value => value.as_f64(),
+    }
+}
+
+/// Collects output rows and returns them sorted. The sort is stable.
+///
+/// If only the first rows are needed, just them are kept in memory, so that `sort by X limit N`
+/// doesn't store the whole input.
+pub struct Sorter<'a> {
+    keys: &'a [SortKey],
+    /// Max-heap, so that the worst row is popped, when there are too many of them
+    rows: BinaryHeap<SortedRow<'a>>,
+    capacity: Option<usize>,
+    pushed_count: usize,
+}
+
+impl<'a> Sorter<'a> {
+    /// Only `capacity` first rows are returned, if it is given.
+    pub fn new(keys: &'a [SortKey], capacity: Option<usize>) -> Self {
+        Self {
+            keys,
+            rows: BinaryHeap::new(),
+            capacity,
+            pushed_count: 0,
+        }
+    }
+
+    /// Pushes the `output` row, that was produced from the `input` one.
+    pub fn push(&mut self, input: &Row, output: Vec<String>) -> Result<(), LineProcessError> {
+        let key = self
+            .keys
+            .iter()
+            .map(|key| key.eval(input))
+            .collect::<Result<_, _>>()?;
+
+        self.rows.push(SortedRow {
+            keys: self.keys,
+            key,
+            // Rows with equal keys are kept in the input order
+            seq: self.pushed_count,
+            row: output,
+        });
+        self.pushed_count += 1;
+
+        if self
+            .capacity
+            .is_some_and(|capacity| self.rows.len() > capacity)
+        {
+            self.rows.pop();
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> impl Iterator<Item = Vec<String>> + 'a {
+        self.rows.into_sorted_vec().into_iter().map(|row| row.row)
+    }
+}
+
+struct SortedRow<'a> {
+    keys: &'a [SortKey],
+    key: Vec<Value>,
+    seq: usize,
+    row: Vec<String>,
+}
+
+impl Ord for SortedRow<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.keys
+            .iter()
+            .zip(self.key.iter().zip(other.key.iter()))
+            .map(|(key, (lhs, rhs))| key.compare(lhs, rhs))
+            .find(|ord| ord.is_ne())
+            .unwrap_or(Ordering::Equal)
+            .then(self.seq.cmp(&other.seq))
+    }
+}
+
+impl PartialOrd for SortedRow<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortedRow<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for SortedRow<'_> {}
+
+/// Compares strings, treating runs of digits as numbers.
+fn natural_cmp(lhs: &str, rhs: &str) -> Ordering {
+    let mut lhs = lhs.chars().peekable();
+    let mut rhs = rhs.chars().peekable();
+
+    loop {
+        match (lhs.peek().copied(), rhs.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let l = take_number(&mut lhs);
+                let r = take_number(&mut rhs);
+                let l_trimmed = l.trim_start_matches('0');
+                let r_trimmed = r.trim_start_matches('0');
+
+                let ord = l_trimmed
+                    .len()
+                    .cmp(&r_trimmed.len())
+                    .then_with(|| l_trimmed.cmp(r_trimmed))
+                    // `01` goes after `1`
+                    .then_with(|| l.len().cmp(&r.len()));
+                if ord.is_ne() {
+                    return ord;
+                }
+            }
+            (Some(l), Some(r)) => {
+                if l != r {
+                    return l.cmp(&r);
+                }
+                lhs.next();
+                rhs.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut ans = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        ans.push(c);
+    }
+    ans
+}
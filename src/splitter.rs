@@ -1,11 +1,13 @@
 use itertools::Itertools;
+use std::io;
 
-use crate::Cli;
+use crate::{row::Row, Cli};
 
 #[derive(Debug)]
 pub enum Splitter<LINES> {
     Whitespace {
         lines: LINES,
+        line_count: usize,
     },
     Smart {
         lines: Vec<String>,
@@ -15,26 +17,30 @@ pub enum Splitter<LINES> {
     },
 }
 
-impl<LINES: Iterator<Item = String>> Splitter<LINES> {
-    pub fn from_cli_and_lines(cli: &Cli, lines: LINES) -> Self {
-        if cli.ws_sep {
-            Self::Whitespace { lines }
+impl<LINES: Iterator<Item = io::Result<String>>> Splitter<LINES> {
+    /// The smart splitter reads the whole input at once, so it may fail here.
+    pub fn from_cli_and_lines(cli: &Cli, lines: LINES) -> io::Result<Self> {
+        Ok(if cli.ws_sep {
+            Self::Whitespace {
+                lines,
+                line_count: 0,
+            }
         } else {
-            let lines = lines.collect_vec();
+            let lines = lines.collect::<io::Result<Vec<_>>>()?;
             let column_ranges = Self::count_column_ranges(&lines);
             Self::Smart {
                 lines,
                 cur: 0,
                 column_ranges,
             }
-        }
+        })
     }
 
     // Returns a vector of pairs `column_ranges`.
     // `column_ranges[i]` is a pair of the start index (inclusive) and end index (exclusize)
     // of i-th column.
     // NOTE: column_ranges.last[1] may be greather that lengths of some strings.
-    fn count_column_ranges(lines: &Vec<String>) -> Vec<(usize, usize)> {
+    fn count_column_ranges(lines: &[String]) -> Vec<(usize, usize)> {
         let max_len = lines.iter().fold(usize::MIN, |ans, s| ans.max(s.len()));
 
         let is_column_whitespace = Self::get_is_column_whitespace_vec(lines);
@@ -64,7 +70,7 @@ impl<LINES: Iterator<Item = String>> Splitter<LINES> {
     /// Returns Vec<uszie> of the same length as the `is_column_whitespace` vec.
     /// `whitespace_prefix[col_id]` is a number of consequential whitespace columns before column
     /// `col_id`, including current.
-    fn get_whitespace_prefix_vec(is_column_whitespace: &Vec<bool>) -> Vec<usize> {
+    fn get_whitespace_prefix_vec(is_column_whitespace: &[bool]) -> Vec<usize> {
         let len = is_column_whitespace.len();
         if len == 0 {
             return vec![];
@@ -85,7 +91,7 @@ impl<LINES: Iterator<Item = String>> Splitter<LINES> {
     /// Returns Vec<bool> of the same length as the longest string.
     /// `is_column_whitespace[col_id] = true` means that `col_id` column contains only whitespace
     /// characters.
-    fn get_is_column_whitespace_vec(lines: &Vec<String>) -> Vec<bool> {
+    fn get_is_column_whitespace_vec(lines: &[String]) -> Vec<bool> {
         let max_len = lines.iter().fold(usize::MIN, |ans, s| ans.max(s.len()));
 
         let mut is_column_whitespace = vec![true; max_len];
@@ -102,14 +108,20 @@ impl<LINES: Iterator<Item = String>> Splitter<LINES> {
     }
 }
 
-impl<LINES: Iterator<Item = String>> Iterator for Splitter<LINES> {
-    type Item = Vec<String>;
+impl<LINES: Iterator<Item = io::Result<String>>> Iterator for Splitter<LINES> {
+    type Item = io::Result<Row>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self {
-            Splitter::Whitespace { lines } => lines
-                .next()
-                .map(|line| line.split_whitespace().map(|s| s.to_string()).collect_vec()),
+            Splitter::Whitespace { lines, line_count } => {
+                let line = match lines.next()? {
+                    Ok(line) => line,
+                    Err(e) => return Some(Err(e)),
+                };
+                *line_count += 1;
+                let cells = line.split_whitespace().map(|s| s.to_string()).collect_vec();
+                Some(Ok(Row::new(cells).with_line_num(Some(*line_count))))
+            }
             Splitter::Smart {
                 cur,
                 lines,
@@ -132,7 +144,7 @@ impl<LINES: Iterator<Item = String>> Iterator for Splitter<LINES> {
                     })
                     .collect_vec();
                 *cur += 1;
-                Some(ans)
+                Some(Ok(Row::new(ans).with_line_num(Some(*cur))))
             }
         }
     }
@@ -0,0 +1,121 @@
+use super::Value;
+
+#[derive(Debug, Clone, Copy)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+/// Meaning of a number
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Plain,
+    Bytes,
+    Seconds,
+    Timestamp,
+}
+
+impl BinaryOp {
+    /// Applies an operator to the values. Returns [`Value::Empty`] if an operation makes no sense
+    /// (e.g. adding a string or two dates) or if the result is not a finite number.
+    ///
+    /// Integer operations result in integers, if possible. Sizes, durations and dates keep their
+    /// units: e.g. size divided by a number is a size, and date minus date is a duration.
+    pub fn apply(self, lhs: &Value, rhs: &Value) -> Value {
+        if let (Value::Int(lhs), Value::Int(rhs)) = (lhs, rhs) {
+            if let Some(ans) = self.apply_int(*lhs, *rhs) {
+                return Value::Int(ans);
+            }
+        }
+
+        let (Some((lhs, lhs_unit)), Some((rhs, rhs_unit))) = (split(lhs), split(rhs)) else {
+            return Value::Empty;
+        };
+        let Some(unit) = self.result_unit(lhs_unit, rhs_unit) else {
+            return Value::Empty;
+        };
+
+        let ans = match self {
+            BinaryOp::Add => lhs + rhs,
+            BinaryOp::Sub => lhs - rhs,
+            BinaryOp::Mul => lhs * rhs,
+            BinaryOp::Div => lhs / rhs,
+            BinaryOp::Rem => lhs % rhs,
+        };
+        if !ans.is_finite() {
+            return Value::Empty;
+        }
+
+        join(ans, unit)
+    }
+
+    /// Returns `None` on overflow or if the result is not an integer.
+    fn apply_int(self, lhs: i64, rhs: i64) -> Option<i64> {
+        match self {
+            BinaryOp::Add => lhs.checked_add(rhs),
+            BinaryOp::Sub => lhs.checked_sub(rhs),
+            BinaryOp::Mul => lhs.checked_mul(rhs),
+            BinaryOp::Div => match lhs.checked_rem(rhs)? {
+                0 => lhs.checked_div(rhs),
+                _ => None,
+            },
+            BinaryOp::Rem => lhs.checked_rem(rhs),
+        }
+    }
+
+    fn result_unit(self, lhs: Unit, rhs: Unit) -> Option<Unit> {
+        use BinaryOp::*;
+        use Unit::*;
+
+        match (self, lhs, rhs) {
+            (Sub, Timestamp, Timestamp) => Some(Seconds),
+            (Add | Sub, Timestamp, Seconds | Plain) => Some(Timestamp),
+            (Add, Seconds | Plain, Timestamp) => Some(Timestamp),
+            (_, Timestamp, _) | (_, _, Timestamp) => None,
+
+            (Add | Sub | Rem | Mul | Div, unit, Plain) => Some(unit),
+            (Add | Sub | Mul, Plain, unit) => Some(unit),
+            (Add | Sub | Rem, lhs, rhs) if lhs == rhs => Some(lhs),
+            (Div, lhs, rhs) if lhs == rhs => Some(Plain),
+            _ => None,
+        }
+    }
+}
+
+impl Value {
+    pub fn neg(&self) -> Value {
+        match self {
+            Value::Int(i) => i
+                .checked_neg()
+                .map_or(Value::Float(-(*i as f64)), Value::Int),
+            Value::Float(f) => Value::Float(-f),
+            Value::Duration(seconds) => Value::Duration(-seconds),
+            _ => Value::Empty,
+        }
+    }
+}
+
+fn split(value: &Value) -> Option<(f64, Unit)> {
+    Some(match value {
+        Value::Int(i) => (*i as f64, Unit::Plain),
+        Value::Float(f) => (*f, Unit::Plain),
+        Value::Size(bytes) => (*bytes as f64, Unit::Bytes),
+        Value::Duration(seconds) => (*seconds as f64, Unit::Seconds),
+        Value::DateTime(timestamp) => (*timestamp as f64, Unit::Timestamp),
+        Value::Empty | Value::Str(_) => return None,
+    })
+}
+
+fn join(x: f64, unit: Unit) -> Value {
+    match unit {
+        Unit::Plain => Value::Float(x),
+        Unit::Bytes if x >= 0.0 => Value::Size(x.round() as u64),
+        // Negative sizes are not supported
+        Unit::Bytes => Value::Float(x),
+        Unit::Seconds => Value::Duration(x.round() as i64),
+        Unit::Timestamp => Value::DateTime(x.round() as i64),
+    }
+}
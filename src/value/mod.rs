@@ -0,0 +1,198 @@
+pub use arith::BinaryOp;
+pub use size::parse_size;
+pub use time::{now, parse_datetime, parse_duration};
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+mod arith;
+mod size;
+mod time;
+
+/// A typed value of a cell or of a constant from a query.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Empty,
+    Int(i64),
+    Float(f64),
+    /// Number of bytes
+    Size(u64),
+    /// Number of seconds
+    Duration(i64),
+    /// Number of seconds since the Unix epoch
+    DateTime(i64),
+    Str(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Empty,
+    Int,
+    Float,
+    Size,
+    Duration,
+    DateTime,
+    Str,
+}
+
+impl Value {
+    /// Parses a string, choosing the narrowest type it fits.
+    pub fn parse(s: &str) -> Self {
+        if s.is_empty() {
+            return Value::Empty;
+        }
+
+        if let Ok(i) = s.parse::<i64>() {
+            return Value::Int(i);
+        }
+
+        // Don't treat "inf" or "NaN" as numbers
+        if s.contains(|c: char| c.is_ascii_digit()) {
+            if let Ok(f) = s.parse::<f64>() {
+                return Value::Float(f);
+            }
+        }
+
+        if let Some(bytes) = parse_size(s) {
+            return Value::Size(bytes);
+        }
+
+        if let Some(seconds) = parse_duration(s) {
+            return Value::Duration(seconds);
+        }
+
+        if let Some(timestamp) = parse_datetime(s) {
+            return Value::DateTime(timestamp);
+        }
+
+        Value::Str(s.to_string())
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Value::Empty => ValueType::Empty,
+            Value::Int(_) => ValueType::Int,
+            Value::Float(_) => ValueType::Float,
+            Value::Size(_) => ValueType::Size,
+            Value::Duration(_) => ValueType::Duration,
+            Value::DateTime(_) => ValueType::DateTime,
+            Value::Str(_) => ValueType::Str,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            Value::Size(bytes) => Some(*bytes as f64),
+            Value::Empty | Value::Duration(_) | Value::DateTime(_) | Value::Str(_) => None,
+        }
+    }
+
+    /// Compares numbers, sizes, durations and dates numerically and everything else
+    /// lexicographically. Empty value is less than any other one.
+    pub fn compare(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Empty, Value::Empty) => Ordering::Equal,
+            (Value::Empty, _) => Ordering::Less,
+            (_, Value::Empty) => Ordering::Greater,
+
+            (Value::Int(lhs), Value::Int(rhs)) => lhs.cmp(rhs),
+            (Value::Size(lhs), Value::Size(rhs)) => lhs.cmp(rhs),
+            (Value::Duration(lhs), Value::Duration(rhs)) => lhs.cmp(rhs),
+            (Value::DateTime(lhs), Value::DateTime(rhs)) => lhs.cmp(rhs),
+            (Value::Str(lhs), Value::Str(rhs)) => lhs.cmp(rhs),
+
+            (lhs, rhs) => match (lhs.as_f64(), rhs.as_f64()) {
+                (Some(lhs), Some(rhs)) => lhs.total_cmp(&rhs),
+                _ => lhs.to_string().cmp(&rhs.to_string()),
+            },
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.compare(other).is_eq()
+    }
+}
+
+impl Eq for Value {}
+
+/// Consistent with [`Value::compare`]: e.g. `1`, `1.0` and `1B` have the same hash.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Value::Empty => 0u8.hash(state),
+            Value::Int(_) | Value::Float(_) | Value::Size(_) => {
+                1u8.hash(state);
+                let x = self.as_f64().unwrap();
+                // `-0.0` equals to `0.0`
+                let x = if x == 0.0 { 0.0 } else { x };
+                x.to_bits().hash(state);
+            }
+            Value::Duration(seconds) => {
+                2u8.hash(state);
+                seconds.hash(state);
+            }
+            Value::DateTime(timestamp) => {
+                3u8.hash(state);
+                timestamp.hash(state);
+            }
+            Value::Str(s) => {
+                4u8.hash(state);
+                s.hash(state);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Empty => Ok(()),
+            Value::Int(i) => write!(f, "{i}"),
+            Value::Float(x) => write!(f, "{x}"),
+            Value::Size(bytes) => write!(f, "{bytes}"),
+            Value::Duration(seconds) => time::fmt_duration(f, *seconds),
+            Value::DateTime(timestamp) => time::fmt_datetime(f, *timestamp),
+            Value::Str(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+impl ValueType {
+    /// Returns the narrowest type, that values of both `self` and `other` types fit.
+    pub fn unify(self, other: ValueType) -> ValueType {
+        use ValueType::*;
+
+        match (self, other) {
+            (Empty, t) | (t, Empty) => t,
+            (lhs, rhs) if lhs == rhs => lhs,
+            (Int, Float) | (Float, Int) => Float,
+            (Int | Float, Size) | (Size, Int | Float) => Size,
+            _ => Str,
+        }
+    }
+
+    /// Infers a type of a column from its cells. It is only used to align numeric columns of a
+    /// pretty table: conditions and sorting use types of single cells, see [`Row::value`].
+    ///
+    /// [`Row::value`]: crate::row::Row::value
+    pub fn infer<'a>(cells: impl IntoIterator<Item = &'a str>) -> ValueType {
+        cells
+            .into_iter()
+            .map(|cell| Value::parse(cell).value_type())
+            .fold(ValueType::Empty, ValueType::unify)
+    }
+
+    pub fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            ValueType::Int | ValueType::Float | ValueType::Size | ValueType::Duration
+        )
+    }
+}
@@ -0,0 +1,34 @@
+/// Parses a human-readable size (e.g. `1.2G`, `300MiB` or `5kB`) and returns a number of bytes.
+///
+/// Single-letter suffixes (`K`, `M`, `G`, ...) and `i`-suffixes (`Ki`, `KiB`, ...) are binary
+/// (powers of 1024), like in `ls -h`, `df -h` or `free -h`. Suffixes with `B` (`kB`, `MB`, ...)
+/// are decimal (powers of 1000).
+pub fn parse_size(s: &str) -> Option<u64> {
+    let unit_start = s.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (number, unit) = s.split_at(unit_start);
+    let number = number.parse::<f64>().ok()?;
+
+    let multiplier = match unit.trim_start() {
+        "B" => 1.0,
+        unit => {
+            let mut chars = unit.chars();
+            let power = match chars.next()? {
+                'k' | 'K' => 1,
+                'M' => 2,
+                'G' => 3,
+                'T' => 4,
+                'P' => 5,
+                'E' => 6,
+                _ => return None,
+            };
+            let base: f64 = match chars.as_str() {
+                "" | "i" | "iB" => 1024.0,
+                "B" => 1000.0,
+                _ => return None,
+            };
+            base.powi(power)
+        }
+    };
+
+    Some((number * multiplier).round() as u64)
+}
@@ -0,0 +1,248 @@
+use std::{
+    fmt,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+const MONTH: i64 = 30 * DAY;
+const YEAR: i64 = 365 * DAY;
+
+/// Returns the current time as a number of seconds since the Unix epoch.
+///
+/// The time is determined once, so that the whole input is processed relative to the same "now".
+pub fn now() -> i64 {
+    static NOW: OnceLock<i64> = OnceLock::new();
+
+    *NOW.get_or_init(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0)
+    })
+}
+
+/// Parses a duration and returns a number of seconds.
+///
+/// Supported formats are:
+/// - clock-like: `01:23` (`MM:SS`), `00:01:23` (`HH:MM:SS`), `2-00:01:23` (`D-HH:MM:SS`)
+/// - unit-based: `5d3h`, `1h 30m`, `45s`, `2 weeks`
+pub fn parse_duration(s: &str) -> Option<i64> {
+    parse_clock(s).or_else(|| parse_units(s))
+}
+
+/// Parses a date with an optional time (e.g. `2026-10-01`, `2026-10-01 12:00`,
+/// `2026-10-01T12:00:00.123+03:00`) or a relative time (e.g. `3 hours ago`) and returns a number
+/// of seconds since the Unix epoch.
+///
+/// Timestamps without an explicit offset are considered to be in UTC.
+pub fn parse_datetime(s: &str) -> Option<i64> {
+    parse_iso_datetime(s).or_else(|| parse_relative(s))
+}
+
+fn parse_clock(s: &str) -> Option<i64> {
+    let (days, s) = match s.split_once('-') {
+        Some((days, s)) => (parse_digits(days)?, s),
+        None => (0, s),
+    };
+
+    let parts = s.split(':').collect::<Vec<_>>();
+    let (h, m, sec) = match parts[..] {
+        [m, sec] => (0, parse_digits(m)?, parse_digits(sec)?),
+        [h, m, sec] => (parse_digits(h)?, parse_digits(m)?, parse_digits(sec)?),
+        _ => return None,
+    };
+
+    if sec >= 60 || (parts.len() == 3 && m >= 60) {
+        return None;
+    }
+
+    Some(days * DAY + h * HOUR + m * MINUTE + sec)
+}
+
+fn parse_units(s: &str) -> Option<i64> {
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut ans = 0.0;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number = rest[..number_len].parse::<f64>().ok()?;
+        rest = rest[number_len..].trim_start();
+
+        let unit_len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "m" | "min" | "mins" | "minute" | "minutes" => MINUTE,
+            "h" | "hr" | "hrs" | "hour" | "hours" => HOUR,
+            "d" | "day" | "days" => DAY,
+            "w" | "week" | "weeks" => WEEK,
+            "mo" | "month" | "months" => MONTH,
+            "y" | "year" | "years" => YEAR,
+            _ => return None,
+        };
+        rest = rest[unit_len..].trim_start();
+
+        ans += number * unit as f64;
+    }
+
+    Some(ans.round() as i64)
+}
+
+/// Parses strings like `3 hours ago` or `About an hour ago`.
+fn parse_relative(s: &str) -> Option<i64> {
+    let s = s.to_lowercase();
+    let s = s.strip_suffix(" ago")?;
+    let s = s.strip_prefix("about ").unwrap_or(s);
+    let s = s.strip_prefix("less than ").unwrap_or(s);
+
+    let duration = match s.split_once(' ') {
+        Some(("a" | "an", unit)) => parse_units(&format!("1 {unit}"))?,
+        _ => parse_units(s)?,
+    };
+
+    Some(now() - duration)
+}
+
+fn parse_iso_datetime(s: &str) -> Option<i64> {
+    let (date, time) = match s.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+
+    let mut date_parts = date.split('-');
+    let year = parse_fixed_digits(date_parts.next()?, 4)?;
+    let month = parse_fixed_digits(date_parts.next()?, 2)?;
+    let day = parse_fixed_digits(date_parts.next()?, 2)?;
+    if date_parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut ans = days_from_civil(year, month, day) * DAY;
+
+    let Some(time) = time else {
+        return Some(ans);
+    };
+
+    let (time, offset) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else if let Some(pos) = time.rfind(['+', '-']) {
+        let (time, offset) = time.split_at(pos);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let offset = offset[1..].replace(':', "");
+        if offset.len() != 4 {
+            return None;
+        }
+        let offset = parse_digits(&offset[..2])? * HOUR + parse_digits(&offset[2..])? * MINUTE;
+        (time, sign * offset)
+    } else {
+        (time, 0)
+    };
+
+    // Fractions of a second are ignored
+    let time = time.split_once('.').map_or(time, |(time, _)| time);
+
+    let mut time_parts = time.split(':');
+    let h = parse_fixed_digits(time_parts.next()?, 2)?;
+    let m = parse_fixed_digits(time_parts.next()?, 2)?;
+    let sec = match time_parts.next() {
+        Some(sec) => parse_fixed_digits(sec, 2)?,
+        None => 0,
+    };
+    if time_parts.next().is_some() || h >= 24 || m >= 60 || sec >= 61 {
+        return None;
+    }
+
+    ans += h * HOUR + m * MINUTE + sec - offset;
+
+    Some(ans)
+}
+
+fn parse_digits(s: &str) -> Option<i64> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn parse_fixed_digits(s: &str, len: usize) -> Option<i64> {
+    if s.len() != len {
+        return None;
+    }
+    parse_digits(s)
+}
+
+/// Returns a number of days since 1970-01-01.
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Inverse of [`days_from_civil`]. Returns (year, month, day).
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+pub fn fmt_duration(f: &mut fmt::Formatter<'_>, seconds: i64) -> fmt::Result {
+    if seconds == 0 {
+        return write!(f, "0s");
+    }
+    if seconds < 0 {
+        write!(f, "-")?;
+    }
+
+    let mut rest = seconds.unsigned_abs();
+    for (unit, name) in [(DAY, 'd'), (HOUR, 'h'), (MINUTE, 'm'), (1, 's')] {
+        let unit = unit as u64;
+        if rest >= unit {
+            write!(f, "{}{name}", rest / unit)?;
+            rest %= unit;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn fmt_datetime(f: &mut fmt::Formatter<'_>, timestamp: i64) -> fmt::Result {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(DAY));
+    let time = timestamp.rem_euclid(DAY);
+    write!(
+        f,
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / HOUR,
+        time % HOUR / MINUTE,
+        time % MINUTE
+    )
+}
@@ -113,6 +113,37 @@ fn basic_condition() {
         "#});
 }
 
+#[test]
+fn condition_column_numbers() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"1 if 2 = "y""#])
+        .write_stdin(indoc! {r#"
+            a x
+            b y
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            b
+        "#});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"1 if $2 * 2 = 4 or 2 = "5""#])
+        .write_stdin(indoc! {r#"
+            a 1
+            b 2
+            c 5
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            b
+            c
+        "#});
+}
+
 #[test]
 fn condition_or() {
     Command::cargo_bin("tx")
@@ -169,3 +200,1510 @@ fn complex_condition() {
             0 1 1
         "#});
 }
+
+#[test]
+fn condition_ordering() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"NAME if AGE > "9" and AGE <= "23""#])
+        .write_stdin(indoc! {r#"
+            ID  NAME          AGE
+            1   Ivan Ivanov   18
+            2   Peter Petrov  23
+            3   John Johnson  50
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            Ivan Ivanov
+            Peter Petrov
+        "#});
+}
+
+#[test]
+fn condition_not_equal_and_lexicographic() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"NAME if ID != "2" and NAME < "J""#])
+        .write_stdin(indoc! {r#"
+            ID  NAME          AGE
+            1   Ivan Ivanov   18
+            2   Peter Petrov  23
+            3   John Johnson  50
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            Ivan Ivanov
+        "#});
+}
+
+#[test]
+fn condition_regex() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"NAME if NAME ~ "^(Ivan|John) " and NAME !~ "son$""#])
+        .write_stdin(indoc! {r#"
+            ID  NAME          AGE
+            1   Ivan Ivanov   18
+            2   Peter Petrov  23
+            3   John Johnson  50
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            Ivan Ivanov
+        "#});
+}
+
+#[test]
+fn condition_regex_escapes() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"NAME if NAME ~ "^\w+ \d+$" and NAME !~ '\t'"#])
+        .write_stdin(indoc! {r#"
+            ID  NAME
+            1   Ivan 18
+            2   Peter Petrov
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            Ivan 18
+        "#});
+}
+
+#[test]
+fn condition_invalid_regex() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"A if A ~ "(""#])
+        .write_stdin(indoc! {r#"
+            A B
+            1 2
+        "#})
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains("invalid regular expression"));
+}
+
+#[test]
+fn condition_not() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"A B C if not (A = "0" or A = "1") and ! B = "0""#])
+        .write_stdin(indoc! {r#"
+            A B C
+            0 0 0
+            1 1 1
+            2 0 0
+            2 1 2
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            2 1 2
+        "#});
+}
+
+#[test]
+fn condition_not_precedence() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"A B if not A = "0" or B = "0""#])
+        .write_stdin(indoc! {r#"
+            A B
+            0 0
+            0 1
+            1 1
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            0 0
+            1 1
+        "#});
+}
+
+#[test]
+fn string_escapes() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([
+            r##"A if B = "say \"hi\"" or B = 'it\'s' or B = r"C:\dir" or B = r#"x"y"# or B = "\u{41}""##,
+        ])
+        .write_stdin(indoc! {r#"
+            A  B
+            1  say "hi"
+            2  it's
+            3  C:\dir
+            4  x"y
+            5  A
+            6  B
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            1
+            2
+            3
+            4
+            5
+        "#});
+}
+
+#[test]
+fn string_invalid_escape() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"A if A = "\q""#])
+        .write_stdin(indoc! {r#"
+            A B
+            1 2
+        "#})
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains("invalid escape sequence"));
+}
+
+#[test]
+fn quoted_column_names() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", r#"`MOUNTED ON` `Use%` if `Use%` > "50""#])
+        .write_stdin(indoc! {r#"
+            FILESYSTEM  Use%  MOUNTED ON
+            /dev/sda1   73    /
+            /dev/sda2   12    /home
+            /dev/sdb1   91    /mnt/data
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            MOUNTED ON Use%
+            /          73
+            /mnt/data  91
+        "#});
+}
+
+#[test]
+fn percent_column_names() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["PID %CPU if %CPU > 5"])
+        .write_stdin(indoc! {r#"
+            PID  %CPU  %MEM
+            1    0.5   1.2
+            42   12.0  3.4
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            42 12.0
+        "#});
+}
+
+#[test]
+fn typed_values() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([
+            "-h",
+            "--align-numbers",
+            r#"NAME SIZE if SIZE >= "9.5" and SIZE != "100""#,
+        ])
+        .write_stdin(indoc! {r#"
+            NAME   SIZE
+            a      9.25
+            bb     10
+            ccc    100
+            dddd   250.5
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            NAME  SIZE
+            bb      10
+            dddd 250.5
+        "#});
+}
+
+#[test]
+fn typed_equality() {
+    let input = indoc! {"
+        NAME  VER   ID   TIME
+        a     1.1   7    1m
+        b     1.10  007  60s
+    "};
+
+    // Against a string literal, texts are compared exactly
+    for (query, output) in [
+        (r#"NAME if VER = "1.1""#, "a\n"),
+        (r#"NAME if ID = "7""#, "a\n"),
+        (r#"NAME if TIME = "1m""#, "a\n"),
+        (r#"NAME if ID != "7""#, "b\n"),
+        // Numbers and columns are compared as values
+        ("NAME if VER = 1.1", "a\nb\n"),
+        ("NAME if ID = 7", "a\nb\n"),
+        ("NAME if ID != 7", ""),
+    ] {
+        Command::cargo_bin("tx")
+            .unwrap()
+            .arg(query)
+            .write_stdin(input)
+            .assert()
+            .success()
+            .stdout(output);
+    }
+}
+
+#[test]
+fn size_condition() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"FILESYSTEM AVAIL if AVAIL < "10G" and AVAIL > "500MB""#])
+        .write_stdin(indoc! {r#"
+            FILESYSTEM  SIZE  AVAIL
+            /dev/sda1   50G   9.5G
+            /dev/sda2   20G   12G
+            /dev/sdb1   1T    800M
+            /dev/sdc1   2G    300M
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            /dev/sda1 9.5G
+            /dev/sdb1 800M
+        "#});
+}
+
+#[test]
+fn size_as_bytes() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--bytes", "--no-pretty", "NAME SIZE"])
+        .write_stdin(indoc! {r#"
+            NAME  SIZE
+            a     1.5K
+            b     2MiB
+            c     3kB
+            d     7
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            a 1536
+            b 2097152
+            c 3000
+            d 7
+        "#});
+}
+
+#[test]
+fn duration_condition() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"NAME if AGE > "2d" or TIME >= "00:10:00""#])
+        .write_stdin(indoc! {r#"
+            NAME   AGE    TIME
+            web    5d3h   00:00:12
+            db     47h    01:02:03
+            cache  3m45s  00:09:59
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            web
+            db
+        "#});
+}
+
+#[test]
+fn datetime_condition() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([
+            r#"ID if STARTED >= "2026-10-01" and STARTED < "2026-10-02T09:00:00+03:00" and STARTED < now"#,
+        ])
+        .write_stdin(indoc! {r#"
+            ID  STARTED
+            1   2026-09-30T23:59:59Z
+            2   2026-10-01 00:00
+            3   2026-10-02T05:59:59Z
+            4   2026-10-02T06:00:00Z
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            2
+            3
+        "#});
+}
+
+#[test]
+fn column_named_now() {
+    // An existing column is preferred to the current time
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", "NAME now if now > 1"])
+        .write_stdin(indoc! {"
+            NAME  now
+            a     1
+            b     2
+        "})
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            NAME now
+            b    2
+        "});
+}
+
+#[test]
+fn relative_datetime_condition() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"NAME if CREATED > "1 day ago""#])
+        .write_stdin(indoc! {r#"
+            NAME   CREATED
+            a      3 hours ago
+            b      About an hour ago
+            c      2 weeks ago
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            a
+            b
+        "#});
+}
+
+#[test]
+fn computed_columns() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([
+            "-h",
+            "--ws-sep",
+            "NAME USED/SIZE*100 (USED + 1)*2 -$4 RSS/1024",
+        ])
+        .write_stdin(indoc! {r#"
+            NAME USED SIZE RSS
+            a 25 100 2048
+            b 3 4 512
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            NAME USED/SIZE*100 (USED + 1)*2 -$4   RSS/1024
+            a    25            52           -2048 2
+            b    75            8            -512  0.5
+        "#});
+}
+
+#[test]
+fn computed_sizes_and_dates() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([
+            "--no-pretty",
+            "--ws-sep",
+            r#"USED+AVAIL END-START START+"1d""#,
+        ])
+        .write_stdin(indoc! {r#"
+            USED AVAIL START END
+            1K 1K 2026-10-01 2026-10-03T12:00:00Z
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            2048 2d12h 2026-10-02T00:00:00Z
+        "#});
+}
+
+#[test]
+fn aliases() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([
+            "-h",
+            "--ws-sep",
+            r#"2 as pid NAME as `Full name` USED/SIZE as "usage" ifname"#,
+        ])
+        .write_stdin(indoc! {r#"
+            NAME PID USED SIZE ifname
+            a 10 1 4 eth0
+            b 20 3 4 lo
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            pid Full name usage ifname
+            10  a         0.25  eth0
+            20  b         0.75  lo
+        "#});
+}
+
+#[test]
+fn functions() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([
+            "--no-pretty",
+            "--ws-sep",
+            r#"lower(NAME) len(CMD) substr(ID, 1, 3) split_part(CMD, ":", 2) concat(NAME, "-", ID)"#,
+        ])
+        .write_stdin(indoc! {r#"
+            NAME CMD ID
+            Alice nginx:master 0071
+            BOB nginx:worker 12
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            alice 12 007 master Alice-0071
+            bob 12 12 worker BOB-12
+        "#});
+}
+
+#[test]
+fn functions_in_conditions() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"NAME if lower(NAME) = "bob" or replace(trim(CMD), "x", "") = "java" and $3 > 5"#])
+        .write_stdin(indoc! {r#"
+            NAME   CMD    ID
+            Alice  jaxva  3
+            BOB    sh     1
+            Carl   java   9
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            BOB
+            Carl
+        "#});
+}
+
+#[test]
+fn unknown_function() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["foo(A)"])
+        .write_stdin(indoc! {r#"
+            A B
+            1 2
+        "#})
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains("unknown function foo"));
+}
+
+#[test]
+fn column_ranges() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--no-pretty", "--ws-sep", "-h", "B..C ..1 4..3"])
+        .write_stdin(indoc! {r#"
+            A B C D E
+            1 2 3 4 5
+            6 7 8 9 0
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            B C A D C
+            2 3 1 4 3
+            7 8 6 9 8
+        "#});
+}
+
+#[test]
+fn invalid_column_numbers() {
+    for query in [
+        "0",
+        "A -0",
+        "A -99999999999999999999999",
+        "99999999999999999999999",
+    ] {
+        Command::cargo_bin("tx")
+            .unwrap()
+            .arg(query)
+            .write_stdin(indoc! {"
+                A  B
+                1  2
+            "})
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains("invalid column number"));
+    }
+}
+
+#[test]
+fn column_ranges_ragged() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--no-pretty", "--ws-sep", "-1 3.. -2..-3"])
+        .write_stdin(indoc! {r#"
+            1 2 3 4 5
+            6 7 8
+            9 0
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            5 3 4 5 4 3
+            8 8 7 6
+            0 0 9
+        "#});
+}
+
+#[test]
+fn all_columns() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", r#"* if USER = "root""#])
+        .write_stdin(indoc! {r#"
+            USER  PID  COMMAND
+            root  1    init
+            bob   42   vim
+            root  7    sshd
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            USER PID COMMAND
+            root 1   init
+            root 7   sshd
+        "#});
+}
+
+#[test]
+fn all_columns_except() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--no-pretty", "* except COMMAND, 1"])
+        .write_stdin(indoc! {r#"
+            USER  PID  COMMAND  TTY
+            root  1    init     ?
+            bob   42   vim      pts/0
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            1 ?
+            42 pts/0
+        "#});
+}
+
+#[test]
+fn all_columns_minus() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--no-pretty", "* - 3"])
+        .write_stdin(indoc! {r#"
+            a b c d
+            e f g h
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            a b d
+            e f h
+        "#});
+}
+
+#[test]
+fn filter_only() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", r#"if STATE = "R""#])
+        .write_stdin(indoc! {r#"
+            PID  STATE  COMMAND
+            1    S      init
+            42   R      vim
+            7    R      sshd
+        "#})
+        .assert()
+        .success()
+        .stdout(indoc! {r#"
+            PID STATE COMMAND
+            42  R     vim
+            7   R     sshd
+        "#});
+}
+
+#[test]
+fn sort_by() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", "NAME SIZE %MEM sort by %MEM desc, SIZE"])
+        .write_stdin(indoc! {"
+            NAME    SIZE  %MEM
+            file10  1K    0.5
+            file2   512   3.1
+            file1   2M    0.5
+            file01  3K    0.5
+        "})
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            NAME   SIZE %MEM
+            file2  512  3.1
+            file10 1K   0.5
+            file01 3K   0.5
+            file1  2M   0.5
+        "});
+}
+
+#[test]
+fn sort_by_modes() {
+    let input = indoc! {"
+        file10  1K
+        file2   512
+        file1   2M
+        file01  3K
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("1 sort by 1 natural")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            file1
+            file01
+            file2
+            file10
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("1 sort by 1 desc")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            file2
+            file10
+            file1
+            file01
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("1 sort by $2 lex")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            file10
+            file1
+            file01
+            file2
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("1 sort by 2 numeric desc")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            file1
+            file01
+            file10
+            file2
+        "});
+}
+
+#[test]
+fn sort_by_mixed_types() {
+    // Numbers and sizes go before strings, whatever the input order is
+    let input = indoc! {"
+        9a
+        10
+        abc
+        5K
+        9
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("1 sort by 1")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            9
+            10
+            5K
+            9a
+            abc
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("1 sort by 1 desc")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            abc
+            9a
+            5K
+            10
+            9
+        "});
+}
+
+#[test]
+fn limit_offset() {
+    let input = indoc! {"
+        NAME    SIZE
+        file10  1K
+        file2   512
+        file1   2M
+        file01  3K
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", "NAME limit 2"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            NAME
+            file10
+            file2
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", "NAME offset 1 limit 2"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            NAME
+            file2
+            file1
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["NAME offset 3"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            file01
+        "});
+}
+
+#[test]
+fn limit_too_large() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("A limit 99999999999999999999999")
+        .write_stdin(indoc! {"
+            A  B
+            1  2
+        "})
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains(indoc! {"
+            number 99999999999999999999999 is too large for `limit` or `offset`
+              |
+            1 | A limit 99999999999999999999999
+              |         ^^^^^^^^^^^^^^^^^^^^^^^"
+        }));
+}
+
+#[test]
+fn sort_by_limit() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", "NAME SIZE sort by SIZE desc limit 2 offset 1"])
+        .write_stdin(indoc! {"
+            NAME    SIZE
+            file10  1K
+            file2   2M
+            file1   2M
+            file01  3K
+            file3   1K
+            file4   2M
+        "})
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            NAME  SIZE
+            file1 2M
+            file4 2M
+        "});
+}
+
+#[test]
+fn group_by() {
+    let input = indoc! {"
+        USER  PID  RSS  STAT
+        root  1    100  S
+        bob   42   2K   R
+        root  7    300  S
+        bob   43   1K   S
+        eve   50   1K   R
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", "USER count() sum(RSS) max(PID) group by USER"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            USER count() sum(RSS) max(PID)
+            root 2       400      7
+            bob  2       3072     43
+            eve  1       1024     50
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([
+            "-h",
+            "USER count_distinct(STAT) as STATES if PID > 1 group by USER having count() > 1",
+        ])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            USER STATES
+            bob  2
+        "});
+
+    // Thresholds are values, not column numbers of the group's first row
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([
+            "-h",
+            "USER sum(RSS) group by USER having sum(RSS) >= 1000 and count() < 2",
+        ])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            USER sum(RSS)
+            eve  1024
+        "});
+}
+
+#[test]
+fn aggregates_without_group_by() {
+    let input = indoc! {"
+        NAME  SIZE
+        a     1
+        b     2
+        c     4
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["count() avg(SIZE) min(NAME)"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            3 2.3333333333333335 a
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"count() if NAME = "x""#])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            0
+        "});
+}
+
+#[test]
+fn aggregate_not_allowed() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["A if count() > 1"])
+        .write_stdin(indoc! {"
+            A  B
+            1  2
+        "})
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains(
+            "aggregate function count can't be used",
+        ));
+}
+
+#[test]
+fn distinct() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", "distinct USER STAT"])
+        .write_stdin(indoc! {"
+            USER  PID  STAT
+            root  1    S
+            bob   42   R
+            root  7    S
+            bob   43   S
+            root  8    S
+        "})
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            USER STAT
+            root S
+            bob  R
+            bob  S
+        "});
+}
+
+#[test]
+fn uniq() {
+    let input = indoc! {"
+        USER  PID  STAT
+        root  1    S
+        root  7    S
+        bob   42   R
+        root  8    S
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["uniq USER"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            root
+            bob
+            root
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", "uniq count USER"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            count USER
+            2     root
+            1     bob
+            1     root
+        "});
+
+    // Like `sort | uniq`, duplicates are removed after sorting
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["-h", "uniq count USER sort by USER limit 1"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            count USER
+            1     bob
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["uniq count USER sort by USER offset 1"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            3 root
+        "});
+}
+
+#[test]
+fn condition_in() {
+    let input = indoc! {"
+        PID  STATE  RSS
+        1    S      100K
+        42   R      2M
+        7    D      1K
+        43   Z      1024
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"PID if STATE in ("R", "D", "Z")"#])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            42
+            7
+            43
+        "});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"PID if STATE not in ("R", "D") and PID != 1"#])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            43
+        "});
+
+    // Values are compared by their types
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args([r#"PID if RSS in ("1KiB", 2097152.0)"#])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            42
+            7
+            43
+        "});
+
+    // Numbers are values, not column numbers
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["STATE if PID in (1, 3, 43)"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            S
+            Z
+        "});
+}
+
+#[test]
+fn condition_between() {
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["PORT if PORT between 8000 and 9000 and PORT != 8080"])
+        .write_stdin(indoc! {"
+            PORT
+            80
+            8000
+            8080
+            8443
+            9000
+            9001
+        "})
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            8000
+            8443
+            9000
+        "});
+
+    // Numbers are values, not column numbers
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["A if B between 2 and 3 and not C between 3 and 30"])
+        .write_stdin(indoc! {"
+            A  B  C
+            a  1  40
+            b  2  5
+            c  3  2
+        "})
+        .assert()
+        .success()
+        .stdout(indoc! {"
+            c
+        "});
+}
+
+#[test]
+fn condition_substrings() {
+    let input = indoc! {"
+        ID  COMMAND        IMAGE
+        1   java -jar app  registry.local/app
+        2   nginx          docker.io/nginx
+        3   Java Server    REGISTRY.local/db
+    "};
+
+    for (query, output) in [
+        (r#"ID if COMMAND contains "java""#, "1\n"),
+        (r#"ID if COMMAND icontains "java""#, "1\n3\n"),
+        (r#"ID if IMAGE startswith "registry.local/""#, "1\n"),
+        (r#"ID if IMAGE istartswith "registry.local/""#, "1\n3\n"),
+        (r#"ID if IMAGE endswith "nginx""#, "2\n"),
+        (r#"ID if IMAGE iendswith "DB""#, "3\n"),
+        (r#"ID if IMAGE endswith COMMAND"#, "2\n"),
+    ] {
+        Command::cargo_bin("tx")
+            .unwrap()
+            .arg(query)
+            .write_stdin(input)
+            .assert()
+            .success()
+            .stdout(output);
+    }
+}
+
+#[test]
+fn condition_ignore_case() {
+    let input = indoc! {"
+        ID  COMMAND
+        1   java -jar app
+        2   nginx
+        3   Java Server
+    "};
+
+    for (query, output) in [
+        (r#"ID if COMMAND =i "NGINX""#, "2\n"),
+        (r#"ID if COMMAND !=i "NGINX""#, "1\n3\n"),
+        (r#"ID if COMMAND ~i "^java""#, "1\n3\n"),
+        (r#"ID if COMMAND !~i "^java""#, "2\n"),
+    ] {
+        Command::cargo_bin("tx")
+            .unwrap()
+            .arg(query)
+            .write_stdin(input)
+            .assert()
+            .success()
+            .stdout(output);
+    }
+}
+
+#[test]
+fn condition_ignore_case_column_i() {
+    // `=i` is always the operator. A column named `i` needs a space after `=`.
+    let input = indoc! {"
+        A  i
+        x  X
+        y  y
+    "};
+
+    for (query, output) in [
+        ("A if A = i", "y\n"),
+        ("A if A =i i", "x\ny\n"),
+        ("A if A=i i", "x\ny\n"),
+        ("A if A != i", "x\n"),
+        ("A if A !=i i", ""),
+    ] {
+        Command::cargo_bin("tx")
+            .unwrap()
+            .arg(query)
+            .write_stdin(input)
+            .assert()
+            .success()
+            .stdout(output);
+    }
+}
+
+#[test]
+fn condition_is_empty() {
+    let input = indoc! {"
+        NAME  VALUE  X
+        a     1      x
+        b            y
+        c     3      z
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("NAME if VALUE is empty")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("b\n");
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("NAME if VALUE is not empty")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("a\nc\n");
+}
+
+#[test]
+fn condition_missing_columns() {
+    let input = indoc! {"
+        a b c
+        d e
+        f c
+        h
+    "};
+
+    for (query, output) in [
+        ("1 if $3 is missing", "d\nf\nh\n"),
+        ("1 if $3 is not missing", "a\n"),
+        // Comparisons with missing columns are false
+        (r#"1 if $3 = "c""#, "a\n"),
+        (r#"1 if $3 != "c""#, ""),
+        (r#"1 if not $3 = "c""#, "d\nf\nh\n"),
+        (r#"1 if $2 = "c" or $3 = "c""#, "a\nf\n"),
+    ] {
+        Command::cargo_bin("tx")
+            .unwrap()
+            .args(["--ws-sep", query])
+            .write_stdin(input)
+            .assert()
+            .success()
+            .stdout(output);
+    }
+}
+
+#[test]
+fn column_not_found_suggestions() {
+    let input = indoc! {"
+        USER  PID  COMMAND
+        root  1    init
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("COMAND")
+        .write_stdin(input)
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains(
+            "column with name COMAND not found. Did you mean COMMAND? \
+            Available columns: USER, PID, COMMAND",
+        ));
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("pid")
+        .write_stdin(input)
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains("Did you mean PID?"));
+}
+
+#[test]
+fn column_name_matching() {
+    let input = indoc! {"
+        COMM  COMMAND  pid  PID
+        a     b        c    d
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--ignore-case", "comm command"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("a b\n");
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--abbrev", "COMMA COMM"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("b a\n");
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--abbrev", "CO"])
+        .write_stdin(input)
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains(
+            "column name CO is ambiguous, it matches: COMM, COMMAND",
+        ));
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--ignore-case", "Pid"])
+        .write_stdin(input)
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains(
+            "column name Pid is ambiguous, it matches: pid, PID",
+        ));
+}
+
+#[test]
+fn query_diagnostics() {
+    let input = indoc! {"
+        USER  PID  COMMAND
+        root  1    init
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("USER lowr(PID)")
+        .write_stdin(input)
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains(indoc! {"
+            unknown function lowr
+              |
+            1 | USER lowr(PID)
+              |      ^^^^^^^^^"
+        }));
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("USER if PID")
+        .write_stdin(input)
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains(indoc! {"
+            syntax error: expected `(`, arithmetic operator or comparison operator
+              |
+            1 | USER if PID
+              |            ^"
+        }));
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg(r#"USER if COMMAND = "a\q""#)
+        .write_stdin(input)
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains(indoc! {r#"
+            1 | USER if COMMAND = "a\q"
+              |                     ^^"#
+        }));
+}
+
+#[test]
+fn exit_codes() {
+    let input = indoc! {"
+        A  B
+        1  2
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("A if B = 3")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--fail-if-empty", "A if B = 3"])
+        .write_stdin(input)
+        .assert()
+        .code(1)
+        .stdout("");
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--fail-if-empty", "A if B = 2"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("1\n");
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("C")
+        .write_stdin(input)
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(predicates::str::starts_with(
+            "error: couldn't parse query string: column with name C not found.",
+        ));
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .arg("$3")
+        .write_stdin(input)
+        .assert()
+        .code(3)
+        .stdout("")
+        .stderr(predicates::str::starts_with(
+            "error: couldn't process a line",
+        ));
+
+    for ws_sep in [false, true] {
+        Command::cargo_bin("tx")
+            .unwrap()
+            .args(ws_sep.then_some("--ws-sep"))
+            .arg("A")
+            .write_stdin(b"A  B\n1  \xff\n".as_slice())
+            .assert()
+            .code(4)
+            .stdout("")
+            .stderr(predicates::str::starts_with(
+                "error: couldn't read a line: stream did not contain valid UTF-8",
+            ));
+    }
+}
+
+#[test]
+fn on_bad_row() {
+    let input = indoc! {"
+        a b c
+        d e
+        f g h
+    "};
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--ws-sep", "1 3"])
+        .write_stdin(input)
+        .assert()
+        .code(3)
+        .stderr(predicates::str::contains(
+            "cannot get column number 3 as there are only 2 columns in line 2.",
+        ));
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--ws-sep", "--on-bad-row=skip", "1 3"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("a c\nf h\n")
+        .stderr("warning: skipped bad rows: 1\n");
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--ws-sep", "--on-bad-row=pad", "1 3"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("a c\nd\nf h\n")
+        .stderr("");
+
+    // Only trailing empty cells are dropped
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--ws-sep", "--on-bad-row=pad", "1 3 2"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("a c b\nd   e\nf h g\n")
+        .stderr("");
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--ws-sep", "--on-bad-row=warn", "1 3"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("a c\nf h\n")
+        .stderr(indoc! {r#"
+            warning: skipping a row: cannot get column number 3 as there are only 2 columns in line 2. Line: "["d", "e"]"
+            warning: skipped bad rows: 1
+        "#});
+
+    Command::cargo_bin("tx")
+        .unwrap()
+        .args(["--ws-sep", "--on-bad-row=skip", "1 count() group by 3"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("a 1\nf 1\n");
+}